@@ -0,0 +1,14 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+
+    let proto_dir = "../src/geyser/proto";
+    println!("cargo:rerun-if-changed={proto_dir}");
+    tonic_build::configure().compile(
+        &[
+            format!("{proto_dir}/geyser.proto"),
+            format!("{proto_dir}/solana-storage.proto"),
+        ],
+        &[proto_dir],
+    )?;
+    Ok(())
+}
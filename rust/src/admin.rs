@@ -0,0 +1,228 @@
+use std::{net::SocketAddr, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Result};
+use solana_sdk::pubkey::Pubkey;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{
+    filters::RejectionLog,
+    state::{DevList, FilterState},
+};
+
+pub const USAGE: &str = "blacklist|whitelist add|remove|contains <pubkey>, \
+                         why <mint>, rejections [count], filter-stats or status";
+
+/// Rejections `rejections` replies with when no count is given.
+const DEFAULT_REJECTIONS: usize = 20;
+
+/// Runs one admin command, e.g. `blacklist add <pubkey>`, and returns the
+/// reply. Rejection queries reply with JSON. List changes write the list
+/// file on a blocking thread.
+pub async fn execute(
+    command: &str,
+    filters: &FilterState,
+    rejections: &RejectionLog,
+) -> Result<String> {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let list = |name: &str| -> Result<&DevList> {
+        match name {
+            "blacklist" => Ok(&filters.blacklist),
+            "whitelist" => Ok(&filters.whitelist),
+            other => bail!("unknown list {other:?}; expected {USAGE}"),
+        }
+    };
+    match words.as_slice() {
+        ["status"] => Ok(format!(
+            "whitelist {} entries, blacklist {} entries",
+            filters.whitelist.len(),
+            filters.blacklist.len()
+        )),
+        ["filter-stats"] => Ok(rejections.summary()),
+        ["why", mint] => {
+            let mint =
+                Pubkey::from_str(mint).map_err(|err| anyhow!("invalid pubkey {mint}: {err}"))?;
+            match rejections.find(&mint) {
+                Some(rejection) => Ok(serde_json::to_string(&rejection)?),
+                None => Ok(format!("no buffered rejection of {mint}")),
+            }
+        }
+        ["rejections"] => Ok(serde_json::to_string(
+            &rejections.recent(DEFAULT_REJECTIONS),
+        )?),
+        ["rejections", count] => {
+            let count = count
+                .parse()
+                .map_err(|err| anyhow!("invalid count {count}: {err}"))?;
+            Ok(serde_json::to_string(&rejections.recent(count))?)
+        }
+        [name, action, developer] => {
+            let list = list(name)?;
+            let developer = Pubkey::from_str(developer)
+                .map_err(|err| anyhow!("invalid pubkey {developer}: {err}"))?;
+            let mutate = |change: fn(&DevList, Pubkey) -> Result<bool>| {
+                let list = list.clone();
+                tokio::task::spawn_blocking(move || change(&list, developer))
+            };
+            let reply = match *action {
+                "add" if mutate(|list, developer| list.insert(developer)).await?? => "added",
+                "add" => "already listed",
+                "remove" if mutate(|list, developer| list.remove(&developer)).await?? => "removed",
+                "remove" => "not listed",
+                "contains" if list.contains(&developer) => "listed",
+                "contains" => "not listed",
+                other => bail!("unknown action {other:?}; expected {USAGE}"),
+            };
+            Ok(format!("{developer} {reply}"))
+        }
+        _ => bail!("expected {USAGE}"),
+    }
+}
+
+/// Serves admin commands, one per line, on a loopback address. Each reply is
+/// one line starting with `ok:` or `error:`.
+pub async fn spawn(
+    listen: &str,
+    filters: FilterState,
+    rejections: RejectionLog,
+) -> Result<JoinHandle<()>> {
+    let address = SocketAddr::from_str(listen)
+        .with_context(|| format!("Invalid admin_listen address {listen}"))?;
+    if !address.ip().is_loopback() {
+        bail!("admin_listen must be a loopback address, got {listen}");
+    }
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Failed to bind admin listener on {listen}"))?;
+    log::info!("Admin commands accepted on {address}");
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let socket = match listener.accept().await {
+                Ok((socket, _)) => socket,
+                Err(err) => {
+                    log::warn!("Admin listener accept failed: {err}");
+                    continue;
+                }
+            };
+            let filters = filters.clone();
+            let rejections = rejections.clone();
+            tokio::spawn(async move {
+                if let Err(err) = serve(socket, &filters, &rejections).await {
+                    log::debug!("Admin connection closed: {err}");
+                }
+            });
+        }
+    }))
+}
+
+async fn serve(socket: TcpStream, filters: &FilterState, rejections: &RejectionLog) -> Result<()> {
+    let (reader, mut writer) = socket.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match execute(&line, filters, rejections).await {
+            Ok(reply) => {
+                log::info!("Admin command: {}", line.trim());
+                format!("ok: {reply}\n")
+            }
+            Err(err) => format!("error: {err:#}\n"),
+        };
+        writer.write_all(reply.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Sends one command to a running bot's admin listener and returns its
+/// reply.
+pub async fn send(address: &str, command: &str) -> Result<String> {
+    let socket = TcpStream::connect(address)
+        .await
+        .with_context(|| format!("Failed to connect to admin listener on {address}"))?;
+    let (reader, mut writer) = socket.into_split();
+    writer.write_all(format!("{command}\n").as_bytes()).await?;
+    let reply = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow!("Admin listener closed without replying"))?;
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn mutates_lists_over_the_admin_socket() {
+        let path = std::env::temp_dir().join(format!("blacklist-{}.txt", std::process::id()));
+        let listed = Pubkey::new_unique();
+        let kept = Pubkey::new_unique();
+        fs::write(
+            &path,
+            format!("# rugged us\n{listed}\n\n{kept} # rugged twice"),
+        )
+        .unwrap();
+        let filters = FilterState {
+            whitelist: DevList::new(vec![], None).unwrap(),
+            blacklist: DevList::new(vec![], Some(path.clone())).unwrap(),
+            rules: Default::default(),
+            twitter_handles: Default::default(),
+        };
+        assert!(filters.is_blacklisted(&listed));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        spawn(&address, filters.clone(), RejectionLog::new(8))
+            .await
+            .unwrap();
+
+        let rugger = Pubkey::new_unique();
+        let reply = send(&address, &format!("blacklist add {rugger}"))
+            .await
+            .unwrap();
+        assert_eq!(reply, format!("ok: {rugger} added"));
+        assert!(filters.is_blacklisted(&rugger));
+        let reply = send(&address, &format!("blacklist remove {listed}"))
+            .await
+            .unwrap();
+        assert_eq!(reply, format!("ok: {listed} removed"));
+        assert!(send(&address, "graylist add x")
+            .await
+            .unwrap()
+            .starts_with("error:"));
+        assert!(spawn("0.0.0.0:0", filters.clone(), RejectionLog::new(8))
+            .await
+            .is_err());
+
+        // Only the removed line is dropped, the added one went on a line of
+        // its own, and our own writes aren't reloads.
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("# rugged us\n\n{kept} # rugged twice\n{rugger}\n")
+        );
+        assert!(!filters.blacklist.reload().unwrap());
+
+        // An external edit is swapped in whole on reload.
+        let replacement = Pubkey::new_unique();
+        fs::write(&path, format!("{replacement}\n")).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        assert!(filters.blacklist.reload().unwrap());
+        assert!(filters.is_blacklisted(&replacement));
+        assert!(!filters.is_blacklisted(&rugger));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::events::pump_fun::{self, TradeEvent};
+
+/// Buys kept per mint; a launch's own slot is over long before this many.
+const MAX_BUYS_PER_MINT: usize = 64;
+
+/// Buys that landed in a launch's create slot, the create transaction's own
+/// buy included.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BundleStats {
+    /// Distinct wallets buying in the create slot.
+    pub buyers: u32,
+    /// Share of the total supply they bought, in percent.
+    pub supply_pct: f64,
+}
+
+#[derive(Debug)]
+struct EarlyBuy {
+    slot: u64,
+    buyer: Pubkey,
+    tokens: u64,
+}
+
+#[derive(Debug)]
+struct EarlyBuys {
+    first_seen: Instant,
+    buys: Vec<EarlyBuy>,
+}
+
+/// The first buys of every mint by slot, for spotting bundled launches where
+/// the creator and sibling wallets buy alongside the create.
+#[derive(Clone, Debug, Default)]
+pub struct BundleTracker {
+    mints: Arc<DashMap<Pubkey, EarlyBuys>>,
+}
+
+impl BundleTracker {
+    pub fn observe(&self, trade: &TradeEvent, slot: Option<u64>) {
+        let Some(slot) = slot.filter(|_| trade.is_buy) else {
+            return;
+        };
+        let mut entry = self.mints.entry(trade.mint).or_insert_with(|| EarlyBuys {
+            first_seen: Instant::now(),
+            buys: Vec::new(),
+        });
+        if entry.buys.len() < MAX_BUYS_PER_MINT {
+            entry.buys.push(EarlyBuy {
+                slot,
+                buyer: trade.user,
+                tokens: trade.token_amount,
+            });
+        }
+    }
+
+    /// Buys of `mint` seen so far in `create_slot`.
+    pub fn stats(&self, mint: &Pubkey, create_slot: u64) -> BundleStats {
+        let Some(entry) = self.mints.get(mint) else {
+            return BundleStats::default();
+        };
+        let mut buyers: Vec<Pubkey> = Vec::new();
+        let mut tokens = 0u64;
+        for buy in entry.buys.iter().filter(|buy| buy.slot == create_slot) {
+            if !buyers.contains(&buy.buyer) {
+                buyers.push(buy.buyer);
+            }
+            tokens = tokens.saturating_add(buy.tokens);
+        }
+        BundleStats {
+            buyers: buyers.len() as u32,
+            supply_pct: tokens as f64 * 100.0 / pump_fun::TOKEN_TOTAL_SUPPLY as f64,
+        }
+    }
+
+    /// Forgets mints first traded more than `max_age` ago.
+    pub fn prune(&self, max_age: Duration) {
+        self.mints
+            .retain(|_, entry| entry.first_seen.elapsed() <= max_age);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy(mint: Pubkey, user: Pubkey, token_amount: u64) -> TradeEvent {
+        TradeEvent {
+            token_amount,
+            ..TradeEvent::for_test(mint, user, true)
+        }
+    }
+
+    #[test]
+    fn counts_same_slot_buyers_and_supply() {
+        let tracker = BundleTracker::default();
+        let mint = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let sibling = Pubkey::new_unique();
+        let supply_pct = |pct: u64| pump_fun::TOKEN_TOTAL_SUPPLY / 100 * pct;
+
+        tracker.observe(&buy(mint, creator, supply_pct(5)), Some(100));
+        tracker.observe(&buy(mint, sibling, supply_pct(10)), Some(100));
+        tracker.observe(&buy(mint, sibling, supply_pct(3)), Some(100));
+        tracker.observe(&buy(mint, Pubkey::new_unique(), supply_pct(20)), Some(101));
+        tracker.observe(&buy(mint, Pubkey::new_unique(), supply_pct(20)), None);
+
+        let stats = tracker.stats(&mint, 100);
+        assert_eq!(stats.buyers, 2);
+        assert!((stats.supply_pct - 18.0).abs() < 1e-9);
+        assert_eq!(
+            tracker.stats(&Pubkey::new_unique(), 100),
+            BundleStats::default()
+        );
+
+        tracker.prune(Duration::ZERO);
+        assert_eq!(tracker.stats(&mint, 100), BundleStats::default());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fs, path::Path, str::FromStr, time::Duration};
+use std::{collections::HashMap, fs, path::Path, str::FromStr, time::Duration};
 
 use anyhow::{anyhow, Context, Result};
 use serde::Deserialize;
@@ -8,6 +8,12 @@ use solana_sdk::{
     signature::{read_keypair_file, Keypair},
 };
 
+use crate::{
+    events::{pump_fun::PUMP_FUN_PROGRAM_ID, queue::DropPolicy, Platform},
+    metadata::MetadataMode,
+    rules::RuleAction,
+};
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PurchaseStrategy {
@@ -18,42 +24,339 @@ pub enum PurchaseStrategy {
 #[derive(Clone, Debug, Deserialize)]
 pub struct FeeConfig {
     pub priority_fee_lamports: Option<u64>,
+    #[allow(dead_code)]
     pub use_jito_tip: Option<bool>,
+    #[allow(dead_code)]
     pub jito_tip_lamports: Option<u64>,
 }
 
+#[allow(dead_code)]
 #[derive(Clone, Debug, Deserialize)]
 pub struct ProfitGuardConfig {
     pub take_profit_factor: Option<f64>,
     pub stop_loss_factor: Option<f64>,
 }
 
+/// Raw message capture for offline debugging. With `replay_path` set, the
+/// live sources are replaced by a replay of that file.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CaptureConfig {
+    pub record_path: Option<String>,
+    pub replay_path: Option<String>,
+    /// Multiplier on the captured timing; `0` replays without delays.
+    pub replay_speed: Option<f64>,
+}
+
+/// Mirrors trades from leader wallets. Buys spend `size_ratio` times what the
+/// leader spent, capped at `max_buy_sol`; sells sell the share of our
+/// position that the leader sold of theirs.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CopyTradeConfig {
+    pub leaders: Vec<String>,
+    /// Falls back to the first WebSocket endpoint.
+    pub ws_url: Option<String>,
+    pub size_ratio: f64,
+    /// Leader buys below this are ignored.
+    pub min_leader_sol: Option<f64>,
+    pub max_buy_sol: Option<f64>,
+    pub mirror_sells: Option<bool>,
+}
+
+impl CopyTradeConfig {
+    /// Lamports to spend mirroring a leader buy, or `None` to skip it.
+    pub fn buy_lamports(&self, leader_lamports: u64) -> Option<u64> {
+        if leader_lamports < sol_to_lamports(self.min_leader_sol.unwrap_or(0.0)) {
+            return None;
+        }
+        let mut lamports = (leader_lamports as f64 * self.size_ratio) as u64;
+        if let Some(max) = self.max_buy_sol {
+            lamports = lamports.min(sol_to_lamports(max));
+        }
+        (lamports > 0).then_some(lamports)
+    }
+
+    pub fn mirror_sells(&self) -> bool {
+        self.mirror_sells.unwrap_or(true)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct DevFilterConfig {
     pub dev_whitelist: Option<Vec<String>>,
     pub dev_blacklist: Option<Vec<String>>,
+    /// Files of one pubkey per line, merged with the lists above and reloaded
+    /// when they change. Admin additions and removals are written back.
+    pub dev_whitelist_file: Option<String>,
+    pub dev_blacklist_file: Option<String>,
+    /// How often the list files are checked for changes.
+    pub list_reload_ms: Option<u64>,
     pub dev_max_tokens_per_min: Option<u32>,
+    /// Reject launches whose developer bought more than this in the create
+    /// transaction.
+    pub max_dev_initial_buy_sol: Option<f64>,
+    /// Reject launches whose developer bought more than this share of the
+    /// supply in the create transaction, in percent.
+    pub max_dev_supply_pct: Option<f64>,
+}
+
+impl DevFilterConfig {
+    pub fn list_reload_interval(&self) -> Duration {
+        Duration::from_millis(self.list_reload_ms.unwrap_or(2_000))
+    }
+}
+
+/// A declarative filter, e.g. `when = 'symbol matches "(?i)cat"'`. Rules run
+/// in order after the developer lists; see `rules` for the condition syntax.
+#[derive(Clone, Debug, Deserialize)]
+pub struct FilterRuleConfig {
+    pub name: String,
+    pub action: RuleAction,
+    pub when: String,
+}
+
+/// Per-developer launch history. Setting the section turns tracking on.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct DevHistoryConfig {
+    /// CSV file the history is kept in across restarts; in memory only if
+    /// unset.
+    pub path: Option<String>,
+    /// A dev selling within this long after launch marks the launch rugged.
+    pub rug_window_secs: Option<u64>,
+    /// Launches neither graduated nor rugged by this age count as abandoned.
+    pub abandon_after_secs: Option<u64>,
+    /// Reject devs whose settled launches were rugged more often than this.
+    pub max_rug_rate_pct: Option<f64>,
+    /// Settled launches needed before `max_rug_rate_pct` applies.
+    pub min_settled_launches: Option<u32>,
+    /// Only accept devs with at least one graduated launch.
+    #[serde(default)]
+    pub require_graduation: bool,
+}
+
+impl DevHistoryConfig {
+    pub fn rug_window(&self) -> Duration {
+        Duration::from_secs(self.rug_window_secs.unwrap_or(60))
+    }
+
+    pub fn abandon_after(&self) -> Duration {
+        Duration::from_secs(self.abandon_after_secs.unwrap_or(6 * 60 * 60))
+    }
+
+    pub fn min_settled_launches(&self) -> u32 {
+        self.min_settled_launches.unwrap_or(3)
+    }
+}
+
+/// Funding-source checks on the developer wallet. Setting the section turns
+/// profiling on.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct DevProfilerConfig {
+    /// Longest a buy waits for a profile; past it the token is filtered
+    /// without one.
+    pub timeout_ms: Option<u64>,
+    /// Earliest transactions of the wallet searched for incoming SOL.
+    pub funding_transactions: Option<usize>,
+    /// `getSignaturesForAddress` pages walked back looking for the wallet's
+    /// first transaction.
+    pub max_history_pages: Option<usize>,
+    /// Reject wallets whose first transaction is more recent than this.
+    pub min_wallet_age_secs: Option<u64>,
+    /// Labels for known funders, e.g. a CEX hot wallet as `"cex"`.
+    #[serde(default)]
+    pub known_funders: HashMap<String, String>,
+    /// Reject developers funded by a wallet with one of these labels.
+    #[serde(default)]
+    pub reject_funder_labels: Vec<String>,
+    /// Blacklist developers funded by a blacklisted wallet; on by default.
+    pub blacklist_funded: Option<bool>,
+}
+
+impl DevProfilerConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.unwrap_or(1_000))
+    }
+
+    pub fn funding_transactions(&self) -> usize {
+        self.funding_transactions.unwrap_or(3)
+    }
+
+    pub fn max_history_pages(&self) -> usize {
+        self.max_history_pages.unwrap_or(3)
+    }
+
+    pub fn blacklist_funded(&self) -> bool {
+        self.blacklist_funded.unwrap_or(true)
+    }
+
+    pub fn known_funders(&self) -> Result<HashMap<Pubkey, String>> {
+        self.known_funders
+            .iter()
+            .map(|(address, label)| {
+                let funder = Pubkey::from_str(address)
+                    .map_err(|err| anyhow!("Invalid known funder {address}: {err}"))?;
+                Ok((funder, label.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Bundled-launch checks on the buys landing in a create's slot. Setting the
+/// section turns tracking on.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct BundleConfig {
+    /// How long after a create is received its slot's trades are awaited
+    /// before filtering.
+    pub wait_ms: Option<u64>,
+    /// Reject launches with more distinct buyers in the create slot.
+    pub max_same_slot_buyers: Option<u32>,
+    /// Reject launches whose create-slot buyers took more of the supply.
+    pub max_same_slot_supply_pct: Option<f64>,
+}
+
+impl BundleConfig {
+    pub fn wait(&self) -> Duration {
+        Duration::from_millis(self.wait_ms.unwrap_or(400))
+    }
+}
+
+/// Off-chain metadata checks. Setting the section turns fetching on.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct MetadataConfig {
+    pub timeout_ms: Option<u64>,
+    /// Gateway `ipfs://` URIs are fetched through.
+    pub ipfs_gateway: Option<String>,
+    pub mode: Option<MetadataMode>,
+    /// Reject tokens without a twitter, telegram or website link, or whose
+    /// metadata couldn't be fetched in time.
+    #[serde(default)]
+    pub require_socials: bool,
+    /// Reject tokens linking to these domains or their subdomains.
+    #[serde(default)]
+    pub blocked_domains: Vec<String>,
+    /// Reject tokens whose twitter account an earlier token already used.
+    #[serde(default)]
+    pub reject_reused_twitter: bool,
+}
+
+impl MetadataConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.unwrap_or(1_500))
+    }
+
+    pub fn ipfs_gateway(&self) -> &str {
+        self.ipfs_gateway
+            .as_deref()
+            .unwrap_or("https://ipfs.io/ipfs/")
+    }
+
+    pub fn mode(&self) -> MetadataMode {
+        self.mode.unwrap_or_default()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct EndpointsConfig {
     pub rpc_http_url: String,
     pub ws_url: Option<String>,
+    /// Extra `logsSubscribe` endpoints pooled with `ws_url`.
+    pub ws_urls: Option<Vec<String>>,
+    pub block_ws_url: Option<String>,
+    pub pumpportal_ws_url: Option<String>,
     pub laserstream_grpc_url: Option<String>,
+    pub laserstream_x_token: Option<String>,
     pub jito_api_url: Option<String>,
     pub nozomi_rpc_url: Option<String>,
 }
 
+/// How the WebSocket source spreads over `ws_url` and `ws_urls`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct WsPoolConfig {
+    /// Endpoints connected at once; the rest stand by for failover.
+    pub connections: Option<usize>,
+    /// An endpoint with no notification for this long is rotated out.
+    pub stale_after_secs: Option<u64>,
+    /// Mean lag behind the fastest pooled endpoint before rotating out.
+    pub max_lag_ms: Option<u64>,
+    /// Disconnects per hour before rotating out.
+    pub max_disconnects_per_hour: Option<u32>,
+}
+
+impl WsPoolConfig {
+    pub fn connections(&self) -> usize {
+        self.connections.unwrap_or(2).max(1)
+    }
+
+    pub fn stale_after(&self) -> Duration {
+        Duration::from_secs(self.stale_after_secs.unwrap_or(10))
+    }
+
+    pub fn max_lag(&self) -> Duration {
+        Duration::from_millis(self.max_lag_ms.unwrap_or(500))
+    }
+
+    pub fn max_disconnects_per_hour(&self) -> u32 {
+        self.max_disconnects_per_hour.unwrap_or(6)
+    }
+}
+
+/// The bounded queue between the sources and the pipeline.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EventQueueConfig {
+    pub capacity: Option<usize>,
+    pub policy: Option<DropPolicy>,
+    /// Creates older than this are shed under the `max_age` policy.
+    pub max_token_age_ms: Option<u64>,
+}
+
+impl EventQueueConfig {
+    pub fn capacity(&self) -> usize {
+        self.capacity.unwrap_or(4096).max(1)
+    }
+
+    pub fn policy(&self) -> DropPolicy {
+        self.policy.unwrap_or_default()
+    }
+
+    pub fn max_token_age(&self) -> Duration {
+        Duration::from_millis(self.max_token_age_ms.unwrap_or(2_000))
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Config {
     pub endpoints: EndpointsConfig,
     pub keypair_path: String,
     pub pump_fun_program: Option<String>,
+    /// Launch platforms the chain sources subscribe to; pump.fun by default.
+    pub platforms: Option<Vec<Platform>>,
     pub purchase_strategy: PurchaseStrategy,
     pub max_slippage_bps: Option<u64>,
     pub fee_config: FeeConfig,
+    #[allow(dead_code)]
     pub profit_guard: Option<ProfitGuardConfig>,
     pub dev_filters: DevFilterConfig,
+    #[serde(default)]
+    pub filter_rules: Vec<FilterRuleConfig>,
+    pub metadata: Option<MetadataConfig>,
+    pub dev_history: Option<DevHistoryConfig>,
+    pub dev_profiler: Option<DevProfilerConfig>,
+    pub bundles: Option<BundleConfig>,
+    #[serde(default)]
+    pub capture: CaptureConfig,
+    pub copy_trade: Option<CopyTradeConfig>,
+    #[serde(default)]
+    pub ws_pool: WsPoolConfig,
+    #[serde(default)]
+    pub event_queue: EventQueueConfig,
+    /// Loopback address the admin command listener binds, e.g.
+    /// `127.0.0.1:7900`; off if unset.
+    pub admin_listen: Option<String>,
+    /// Rejections kept for `why`/`rejections` admin queries.
+    pub recent_rejections: Option<usize>,
+    /// Creates processed at once; more wait for a free slot.
+    pub max_concurrent_events: Option<usize>,
+    /// Creates recovered after a feed gap are skipped past this age.
+    pub max_backfill_age_secs: Option<u64>,
     pub dry_run: Option<bool>,
     pub log_level: Option<String>,
     pub blockhash_refresh_ms: Option<u64>,
@@ -84,6 +387,41 @@ impl Config {
         parse_pubkeys(self.dev_filters.dev_blacklist.clone())
     }
 
+    pub fn tracks(&self, platform: Platform) -> bool {
+        match &self.platforms {
+            Some(platforms) => platforms.contains(&platform),
+            None => platform == Platform::PumpFun,
+        }
+    }
+
+    pub fn copy_leaders(&self) -> Result<Vec<Pubkey>> {
+        parse_pubkeys(self.copy_trade.as_ref().map(|copy| copy.leaders.clone()))
+    }
+
+    /// `ws_url` followed by `ws_urls`, without repeats.
+    pub fn ws_endpoints(&self) -> Vec<String> {
+        let mut endpoints: Vec<String> = Vec::new();
+        let extra = self.endpoints.ws_urls.iter().flatten();
+        for endpoint in self.endpoints.ws_url.iter().chain(extra) {
+            if !endpoints.contains(endpoint) {
+                endpoints.push(endpoint.clone());
+            }
+        }
+        endpoints
+    }
+
+    pub fn max_concurrent_events(&self) -> usize {
+        self.max_concurrent_events.unwrap_or(16).max(1)
+    }
+
+    pub fn recent_rejections(&self) -> usize {
+        self.recent_rejections.unwrap_or(1_024)
+    }
+
+    pub fn max_backfill_age(&self) -> Duration {
+        Duration::from_secs(self.max_backfill_age_secs.unwrap_or(30))
+    }
+
     pub fn blockhash_refresh_interval(&self) -> Duration {
         Duration::from_millis(self.blockhash_refresh_ms.unwrap_or(350))
     }
@@ -92,6 +430,10 @@ impl Config {
         Duration::from_millis(self.balance_refresh_ms.unwrap_or(1500))
     }
 
+    pub fn log_level(&self) -> &str {
+        self.log_level.as_deref().unwrap_or("info")
+    }
+
     pub fn dry_run(&self) -> bool {
         self.dry_run.unwrap_or(false)
     }
@@ -114,11 +456,10 @@ impl Config {
     }
 
     pub fn program_id(&self) -> Result<Pubkey> {
-        let id = self
-            .pump_fun_program
-            .clone()
-            .unwrap_or_else(|| "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp".to_string());
-        Pubkey::from_str(&id).map_err(|err| anyhow!("Invalid pump.fun program id: {err}"))
+        let Some(id) = self.pump_fun_program.as_deref() else {
+            return Ok(PUMP_FUN_PROGRAM_ID);
+        };
+        Pubkey::from_str(id).map_err(|err| anyhow!("Invalid pump.fun program id: {err}"))
     }
 }
 
@@ -0,0 +1,487 @@
+use std::{
+    collections::{hash_map, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{mpsc as std_mpsc, Arc},
+    thread::JoinHandle,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use dashmap::{mapref::entry::Entry, DashMap, DashSet};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    config::DevHistoryConfig,
+    events::{pump_fun::TradeEvent, TokenEvent},
+};
+
+const CSV_HEADER: &str = "mint,developer,launched_at,outcome";
+
+/// How a launch ended, as far as the bot saw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchOutcome {
+    /// Migrated off the bonding curve.
+    Graduated,
+    /// The developer sold within the rug window.
+    Rugged,
+    /// Neither of the above within `abandon_after_secs`.
+    Abandoned,
+}
+
+impl LaunchOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            LaunchOutcome::Graduated => "graduated",
+            LaunchOutcome::Rugged => "rugged",
+            LaunchOutcome::Abandoned => "abandoned",
+        }
+    }
+}
+
+impl FromStr for LaunchOutcome {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value {
+            "graduated" => LaunchOutcome::Graduated,
+            "rugged" => LaunchOutcome::Rugged,
+            "abandoned" => LaunchOutcome::Abandoned,
+            other => bail!("unknown outcome {other:?}"),
+        })
+    }
+}
+
+/// One token a developer created.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Launch {
+    pub mint: Pubkey,
+    pub developer: Pubkey,
+    /// Unix seconds.
+    pub launched_at: u64,
+    /// `None` while the launch is still running.
+    pub outcome: Option<LaunchOutcome>,
+}
+
+impl Launch {
+    fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            self.mint,
+            self.developer,
+            self.launched_at,
+            self.outcome.map(LaunchOutcome::as_str).unwrap_or_default()
+        )
+    }
+
+    fn from_csv(line: &str) -> Result<Self> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [mint, developer, launched_at, outcome] = fields.as_slice() else {
+            bail!("expected {} fields, got {}", 4, fields.len());
+        };
+        Ok(Self {
+            mint: Pubkey::from_str(mint).map_err(|err| anyhow!("invalid mint: {err}"))?,
+            developer: Pubkey::from_str(developer)
+                .map_err(|err| anyhow!("invalid developer: {err}"))?,
+            launched_at: launched_at.parse().context("invalid launched_at")?,
+            outcome: match *outcome {
+                "" => None,
+                outcome => Some(outcome.parse()?),
+            },
+        })
+    }
+}
+
+/// Launch counts of one developer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DevProfile {
+    pub launches: u32,
+    pub graduated: u32,
+    pub rugged: u32,
+    pub abandoned: u32,
+}
+
+impl DevProfile {
+    /// Launches with a known outcome.
+    pub fn settled(&self) -> u32 {
+        self.graduated + self.rugged + self.abandoned
+    }
+
+    /// Share of settled launches that were rugged, in percent.
+    pub fn rug_rate_pct(&self) -> Option<f64> {
+        let settled = self.settled();
+        (settled > 0).then(|| self.rugged as f64 * 100.0 / settled as f64)
+    }
+
+    fn add(&mut self, outcome: LaunchOutcome) {
+        match outcome {
+            LaunchOutcome::Graduated => self.graduated += 1,
+            LaunchOutcome::Rugged => self.rugged += 1,
+            LaunchOutcome::Abandoned => self.abandoned += 1,
+        }
+    }
+}
+
+/// Launch counts per developer and the launches still running. A launch is
+/// folded into its developer's profile and dropped once it settles; only its
+/// mint is kept, so a late redelivery isn't counted again. The
+/// history is kept in a CSV file that changes are appended to and which is
+/// compacted on startup.
+#[derive(Clone, Debug)]
+pub struct DevHistory {
+    launches: Arc<DashMap<Pubkey, Launch>>,
+    settled: Arc<DashSet<Pubkey>>,
+    profiles: Arc<DashMap<Pubkey, DevProfile>>,
+    rug_window: Duration,
+    abandon_after: Duration,
+    writer: Option<std_mpsc::Sender<String>>,
+}
+
+impl DevHistory {
+    fn new(config: &DevHistoryConfig) -> Self {
+        Self {
+            launches: Arc::new(DashMap::new()),
+            settled: Arc::new(DashSet::new()),
+            profiles: Arc::new(DashMap::new()),
+            rug_window: config.rug_window(),
+            abandon_after: config.abandon_after(),
+            writer: None,
+        }
+    }
+
+    /// Loads the history, compacts its file and appends every change to it
+    /// from then on. Writes happen on a dedicated thread.
+    pub fn open(config: &DevHistoryConfig) -> Result<Self> {
+        Self::open_with_writer(config).map(|(history, _)| history)
+    }
+
+    /// Like `open`, also returning the writer thread. It exits once every
+    /// clone of the history is dropped and all their changes are written.
+    fn open_with_writer(config: &DevHistoryConfig) -> Result<(Self, Option<JoinHandle<()>>)> {
+        let Some(path) = &config.path else {
+            return Ok((Self::new(config), None));
+        };
+        let launches = if Path::new(path).exists() {
+            read_csv(Path::new(path))?
+        } else {
+            HashMap::new()
+        };
+        write_csv(path, &launches)?;
+        let mut history = Self::new(config);
+        history.extend(launches.into_values());
+
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open dev history {path}"))?;
+        let (tx, rx) = std_mpsc::channel::<String>();
+        let writer = std::thread::spawn(move || {
+            let mut writer = BufWriter::new(file);
+            while let Ok(line) = rx.recv() {
+                let mut pending = Some(line);
+                while let Some(line) = pending {
+                    if let Err(err) = writeln!(writer, "{line}") {
+                        log::warn!("Failed to write dev history: {err}");
+                    }
+                    pending = rx.try_recv().ok();
+                }
+                let _ = writer.flush();
+            }
+        });
+        log::info!(
+            "Loaded {} launches ({} running) by {} developers from {path}",
+            history
+                .profiles
+                .iter()
+                .map(|profile| profile.launches)
+                .sum::<u32>(),
+            history.launches.len(),
+            history.profiles.len()
+        );
+        history.writer = Some(tx);
+        Ok((history, Some(writer)))
+    }
+
+    pub fn profile(&self, developer: &Pubkey) -> DevProfile {
+        self.profiles
+            .get(developer)
+            .map(|profile| *profile)
+            .unwrap_or_default()
+    }
+
+    pub fn record_launch(&self, event: &TokenEvent) {
+        let launched_at = event.received_at - event.backfill_age.unwrap_or_default();
+        let launch = Launch {
+            mint: event.mint,
+            developer: event.developer,
+            launched_at: unix_secs(launched_at),
+            outcome: None,
+        };
+        // Checked under the entry's lock, which `settle` waits on between
+        // marking a mint settled and dropping its launch.
+        let Entry::Vacant(vacant) = self.launches.entry(launch.mint) else {
+            return;
+        };
+        if self.settled.contains(&launch.mint) {
+            return;
+        }
+        self.profiles.entry(launch.developer).or_default().launches += 1;
+        self.persist(vacant.insert(launch).to_csv());
+    }
+
+    /// Marks the launch rugged if this is its developer selling inside the
+    /// rug window.
+    pub fn observe_trade(&self, trade: &TradeEvent) {
+        if trade.is_buy {
+            return;
+        }
+        let rugged = self.launches.get(&trade.mint).is_some_and(|launch| {
+            launch.developer == trade.user
+                && trade.timestamp.saturating_sub(launch.launched_at as i64)
+                    <= self.rug_window.as_secs() as i64
+        });
+        if rugged {
+            self.settle(&trade.mint, LaunchOutcome::Rugged);
+        }
+    }
+
+    pub fn graduated(&self, mint: &Pubkey) {
+        self.settle(mint, LaunchOutcome::Graduated);
+    }
+
+    /// Settles launches still running `abandon_after_secs` after launch as
+    /// abandoned; returns how many.
+    pub fn sweep(&self, now: SystemTime) -> usize {
+        let cutoff = unix_secs(now).saturating_sub(self.abandon_after.as_secs());
+        let abandoned: Vec<Pubkey> = self
+            .launches
+            .iter()
+            .filter(|launch| launch.launched_at < cutoff)
+            .map(|launch| launch.mint)
+            .collect();
+        for mint in &abandoned {
+            self.settle(mint, LaunchOutcome::Abandoned);
+        }
+        abandoned.len()
+    }
+
+    /// Folds a running launch into its developer's profile and drops it; a
+    /// settled launch keeps its outcome. Changes are persisted.
+    fn settle(&self, mint: &Pubkey, outcome: LaunchOutcome) {
+        if !self.launches.contains_key(mint) || !self.settled.insert(*mint) {
+            return;
+        }
+        let Some((_, launch)) = self.launches.remove(mint) else {
+            return;
+        };
+        self.profiles
+            .entry(launch.developer)
+            .or_default()
+            .add(outcome);
+        self.persist(
+            Launch {
+                outcome: Some(outcome),
+                ..launch
+            }
+            .to_csv(),
+        );
+    }
+
+    /// Adds launches read from a file, without persisting them.
+    fn extend(&self, launches: impl IntoIterator<Item = Launch>) {
+        for launch in launches {
+            let mut profile = self.profiles.entry(launch.developer).or_default();
+            profile.launches += 1;
+            match launch.outcome {
+                Some(outcome) => {
+                    profile.add(outcome);
+                    self.settled.insert(launch.mint);
+                }
+                None => {
+                    self.launches.insert(launch.mint, launch);
+                }
+            }
+        }
+    }
+
+    fn persist(&self, line: String) {
+        if let Some(writer) = &self.writer {
+            let _ = writer.send(line);
+        }
+    }
+}
+
+/// Merges the launches in `from` into the history file `into`; returns how
+/// many were read.
+pub fn import_csv(from: impl AsRef<Path>, into: impl AsRef<Path>) -> Result<usize> {
+    let into = into.as_ref();
+    let mut launches = if into.exists() {
+        read_csv(into)?
+    } else {
+        HashMap::new()
+    };
+    let imported = read_csv(from.as_ref())?;
+    let count = imported.len();
+    for launch in imported.into_values() {
+        merge(&mut launches, launch);
+    }
+    write_csv(into, &launches)?;
+    Ok(count)
+}
+
+/// Writes the launches in the history file `from` to `to`, compacted;
+/// returns how many were written.
+pub fn export_csv(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<usize> {
+    write_csv(to, &read_csv(from.as_ref())?)
+}
+
+/// Adds a launch or settles a running one; a settled launch keeps its
+/// outcome.
+fn merge(launches: &mut HashMap<Pubkey, Launch>, launch: Launch) {
+    match launches.entry(launch.mint) {
+        hash_map::Entry::Vacant(vacant) => {
+            vacant.insert(launch);
+        }
+        hash_map::Entry::Occupied(mut occupied) => {
+            if occupied.get().outcome.is_none() && launch.outcome.is_some() {
+                occupied.insert(launch);
+            }
+        }
+    }
+}
+
+/// Launches in a CSV file in the export format, one per mint.
+fn read_csv(path: &Path) -> Result<HashMap<Pubkey, Launch>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open dev history {}", path.display()))?;
+    let mut launches = HashMap::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with("mint,") {
+            continue;
+        }
+        let launch = Launch::from_csv(&line)
+            .map_err(|err| anyhow!("{}:{}: {err}", path.display(), index + 1))?;
+        merge(&mut launches, launch);
+    }
+    Ok(launches)
+}
+
+/// Writes `launches` to `path` as CSV, replacing it atomically; returns how
+/// many were written.
+fn write_csv(path: impl AsRef<Path>, launches: &HashMap<Pubkey, Launch>) -> Result<usize> {
+    let path = path.as_ref();
+    let mut staging = PathBuf::from(path);
+    staging.as_mut_os_string().push(".tmp");
+    let file = File::create(&staging)
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "{CSV_HEADER}")?;
+    let mut launches: Vec<&Launch> = launches.values().collect();
+    launches.sort_by_key(|launch| (launch.launched_at, launch.mint));
+    for launch in &launches {
+        writeln!(writer, "{}", launch.to_csv())?;
+    }
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&staging, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(launches.len())
+}
+
+fn unix_secs(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch(developer: Pubkey, at_secs: u64) -> TokenEvent {
+        TokenEvent {
+            received_at: UNIX_EPOCH + Duration::from_secs(at_secs),
+            ..TokenEvent::for_test(Pubkey::new_unique(), developer)
+        }
+    }
+
+    fn sell(mint: Pubkey, user: Pubkey, timestamp: i64) -> TradeEvent {
+        TradeEvent {
+            timestamp,
+            ..TradeEvent::for_test(mint, user, false)
+        }
+    }
+
+    #[test]
+    fn settles_outcomes_and_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("dev-history-{}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let config = DevHistoryConfig {
+            path: Some(path.display().to_string()),
+            rug_window_secs: Some(60),
+            abandon_after_secs: Some(3_600),
+            ..DevHistoryConfig::default()
+        };
+        let (history, writer) = DevHistory::open_with_writer(&config).unwrap();
+        let developer = Pubkey::new_unique();
+
+        let rugged = launch(developer, 1_000);
+        history.record_launch(&rugged);
+        // Someone else selling, then the dev selling too late, isn't a rug.
+        history.observe_trade(&sell(rugged.mint, Pubkey::new_unique(), 1_010));
+        let held = launch(developer, 1_000);
+        history.record_launch(&held);
+        history.observe_trade(&sell(held.mint, developer, 1_100));
+        history.observe_trade(&sell(rugged.mint, developer, 1_030));
+
+        let graduated = launch(developer, 2_000);
+        history.record_launch(&graduated);
+        history.graduated(&graduated.mint);
+        history.record_launch(&launch(developer, 4_000));
+
+        assert_eq!(history.sweep(UNIX_EPOCH + Duration::from_secs(5_000)), 1);
+        let profile = history.profile(&developer);
+        assert_eq!(
+            profile,
+            DevProfile {
+                launches: 4,
+                graduated: 1,
+                rugged: 1,
+                abandoned: 1,
+            }
+        );
+        assert_eq!(profile.rug_rate_pct(), Some(100.0 / 3.0));
+        // Only the running launch is still held, and a settled one
+        // delivered again isn't a new launch.
+        assert_eq!(history.launches.len(), 1);
+        history.record_launch(&rugged);
+        assert_eq!(history.profile(&developer), profile);
+
+        // The writer thread appends asynchronously; it's done once the last
+        // clone is dropped.
+        drop(history);
+        writer.unwrap().join().unwrap();
+        let reloaded = DevHistory::open(&config).unwrap();
+        assert_eq!(reloaded.profile(&developer), profile);
+        assert_eq!(reloaded.launches.len(), 1);
+        reloaded.record_launch(&graduated);
+        assert_eq!(reloaded.profile(&developer), profile);
+
+        let export = path.with_extension("export.csv");
+        assert_eq!(export_csv(&path, &export).unwrap(), 4);
+        let imported = path.with_extension("import.csv");
+        let _ = fs::remove_file(&imported);
+        assert_eq!(import_csv(&export, &imported).unwrap(), 4);
+        // Importing the same launches again changes nothing.
+        assert_eq!(import_csv(&export, &imported).unwrap(), 4);
+        let imported_config = DevHistoryConfig {
+            path: Some(imported.display().to_string()),
+            ..DevHistoryConfig::default()
+        };
+        let imported_history = DevHistory::open(&imported_config).unwrap();
+        assert_eq!(imported_history.profile(&developer), profile);
+        for file in [&path, &export, &imported] {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use futures::{stream, StreamExt};
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::config::DevProfilerConfig;
+
+/// `getSignaturesForAddress` page size (the RPC maximum).
+const PAGE_SIZE: usize = 1_000;
+/// Profiled wallets kept in the cache; the oldest are evicted beyond this.
+const CACHE_CAPACITY: usize = 16_384;
+
+/// Where a developer wallet came from, as far as its earliest transactions
+/// tell.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FundingProfile {
+    /// Block time of the oldest transaction found, Unix seconds.
+    pub first_activity: Option<i64>,
+    /// Whether the history was walked back to the wallet's first transaction.
+    /// If not, the wallet is older than `first_activity` says.
+    pub complete: bool,
+    /// Wallets that sent SOL in the earliest transactions, oldest first.
+    pub funders: Vec<Pubkey>,
+}
+
+impl FundingProfile {
+    pub fn wallet_age(&self, now: SystemTime) -> Option<Duration> {
+        let first = UNIX_EPOCH + Duration::from_secs(self.first_activity?.max(0) as u64);
+        Some(now.duration_since(first).unwrap_or_default())
+    }
+
+    /// The wallet's first funder.
+    pub fn funder(&self) -> Option<Pubkey> {
+        self.funders.first().copied()
+    }
+}
+
+/// Traces developer wallets' funding over RPC, caching by wallet.
+#[derive(Clone)]
+pub struct DevProfiler {
+    rpc: Arc<RpcClient>,
+    timeout: Duration,
+    funding_transactions: usize,
+    max_history_pages: usize,
+    known_funders: Arc<HashMap<Pubkey, String>>,
+    cache: Arc<DashMap<Pubkey, Arc<FundingProfile>>>,
+    order: Arc<Mutex<VecDeque<Pubkey>>>,
+}
+
+impl DevProfiler {
+    pub fn new(rpc: Arc<RpcClient>, config: &DevProfilerConfig) -> Result<Self> {
+        Ok(Self {
+            rpc,
+            timeout: config.timeout(),
+            funding_transactions: config.funding_transactions(),
+            max_history_pages: config.max_history_pages(),
+            known_funders: Arc::new(config.known_funders()?),
+            cache: Arc::new(DashMap::new()),
+            order: Arc::new(Mutex::new(VecDeque::new())),
+        })
+    }
+
+    /// The configured label of a known funder, e.g. `"cex"`.
+    pub fn label(&self, funder: &Pubkey) -> Option<&str> {
+        self.known_funders.get(funder).map(String::as_str)
+    }
+
+    /// Profiles `wallet`, failing past the configured timeout.
+    pub async fn profile(&self, wallet: &Pubkey) -> Result<Arc<FundingProfile>> {
+        if let Some(profile) = self.cache.get(wallet) {
+            return Ok(profile.clone());
+        }
+        let profile = tokio::time::timeout(self.timeout, self.trace(wallet))
+            .await
+            .map_err(|_| anyhow!("Profiling {wallet} timed out after {:?}", self.timeout))??;
+        let profile = Arc::new(profile);
+        if self.cache.insert(*wallet, profile.clone()).is_none() {
+            let mut order = self.order.lock();
+            order.push_back(*wallet);
+            while order.len() > CACHE_CAPACITY {
+                if let Some(oldest) = order.pop_front() {
+                    self.cache.remove(&oldest);
+                }
+            }
+        }
+        Ok(profile)
+    }
+
+    async fn trace(&self, wallet: &Pubkey) -> Result<FundingProfile> {
+        let (signatures, complete) = self.earliest_signatures(wallet).await?;
+        let first_activity = signatures.first().and_then(|(_, block_time)| *block_time);
+
+        let rpc = &self.rpc;
+        let funders: Vec<Option<Pubkey>> =
+            stream::iter(signatures.into_iter().take(self.funding_transactions))
+                .map(|(signature, _)| async move {
+                    let config = json!({
+                        "encoding": "json",
+                        "commitment": "confirmed",
+                        "maxSupportedTransactionVersion": 0
+                    });
+                    let params = json!([signature, config]);
+                    match rpc.send::<Value>(RpcRequest::GetTransaction, params).await {
+                        Ok(transaction) => funding_source(&transaction, wallet),
+                        Err(err) => {
+                            log::debug!("Failed to fetch {signature} profiling {wallet}: {err}");
+                            None
+                        }
+                    }
+                })
+                .buffered(self.funding_transactions.max(1))
+                .collect()
+                .await;
+
+        let mut profile = FundingProfile {
+            first_activity,
+            complete,
+            funders: Vec::new(),
+        };
+        for funder in funders.into_iter().flatten() {
+            if !profile.funders.contains(&funder) {
+                profile.funders.push(funder);
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Successful signatures of `wallet` with their block times, oldest
+    /// first, and whether the walk reached the first one.
+    async fn earliest_signatures(
+        &self,
+        wallet: &Pubkey,
+    ) -> Result<(Vec<(String, Option<i64>)>, bool)> {
+        let address = wallet.to_string();
+        let mut signatures = Vec::new();
+        let mut before: Option<String> = None;
+
+        for _ in 0..self.max_history_pages {
+            let mut config = json!({ "limit": PAGE_SIZE, "commitment": "confirmed" });
+            if let Some(before) = &before {
+                config["before"] = json!(before);
+            }
+            let page: Vec<Value> = self
+                .rpc
+                .send(
+                    RpcRequest::GetSignaturesForAddress,
+                    json!([address, config]),
+                )
+                .await?;
+            let page_len = page.len();
+            for entry in page {
+                let Some(signature) = entry.get("signature").and_then(Value::as_str) else {
+                    continue;
+                };
+                before = Some(signature.to_string());
+                if entry.get("err").is_some_and(|err| !err.is_null()) {
+                    continue;
+                }
+                let block_time = entry.get("blockTime").and_then(Value::as_i64);
+                signatures.push((signature.to_string(), block_time));
+            }
+            if page_len < PAGE_SIZE {
+                signatures.reverse();
+                return Ok((signatures, true));
+            }
+        }
+        signatures.reverse();
+        Ok((signatures, false))
+    }
+}
+
+/// The account that paid the most into `wallet` in a `json`-encoded
+/// `getTransaction` result, if the wallet's balance went up.
+pub fn funding_source(transaction: &Value, wallet: &Pubkey) -> Option<Pubkey> {
+    let mut keys: Vec<&str> = transaction
+        .pointer("/transaction/message/accountKeys")?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    for table in ["writable", "readonly"] {
+        let loaded = transaction
+            .pointer(&format!("/meta/loadedAddresses/{table}"))
+            .and_then(Value::as_array);
+        keys.extend(loaded.into_iter().flatten().filter_map(Value::as_str));
+    }
+    let balances = |field: &str| -> Option<Vec<i128>> {
+        transaction
+            .pointer(&format!("/meta/{field}"))?
+            .as_array()?
+            .iter()
+            .map(|balance| balance.as_u64().map(i128::from))
+            .collect()
+    };
+    let pre = balances("preBalances")?;
+    let post = balances("postBalances")?;
+    let delta = |index: usize| Some(*post.get(index)? - *pre.get(index)?);
+
+    let wallet = wallet.to_string();
+    let wallet_index = keys.iter().position(|key| *key == wallet)?;
+    if delta(wallet_index)? <= 0 {
+        return None;
+    }
+    let (funder, _) = keys
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != wallet_index)
+        .filter_map(|(index, key)| Some((*key, delta(index)?)))
+        .filter(|(_, delta)| *delta < 0)
+        .min_by_key(|(_, delta)| *delta)?;
+    Pubkey::from_str(funder).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_largest_sender_into_the_wallet() {
+        let exchange = Pubkey::new_unique();
+        let developer = Pubkey::new_unique();
+        let transaction = json!({
+            "blockTime": 1_700_000_000,
+            "meta": {
+                "preBalances": [50_000_000_000u64, 0, 1],
+                "postBalances": [47_999_995_000u64, 2_000_000_000, 1],
+                "loadedAddresses": { "writable": [], "readonly": [] }
+            },
+            "transaction": {
+                "message": {
+                    "accountKeys": [
+                        exchange.to_string(),
+                        developer.to_string(),
+                        "11111111111111111111111111111111"
+                    ]
+                }
+            }
+        });
+
+        assert_eq!(funding_source(&transaction, &developer), Some(exchange));
+        // The sender's side of the same transfer isn't funding.
+        assert_eq!(funding_source(&transaction, &exchange), None);
+        assert_eq!(funding_source(&Value::Null, &developer), None);
+
+        let profile = FundingProfile {
+            first_activity: Some(1_700_000_000),
+            complete: true,
+            funders: vec![exchange],
+        };
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_090);
+        assert_eq!(profile.wallet_age(now), Some(Duration::from_secs(90)));
+        assert_eq!(profile.funder(), Some(exchange));
+    }
+}
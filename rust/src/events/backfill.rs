@@ -0,0 +1,207 @@
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use serde_json::{json, Value};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use super::{
+    dedup::EventSender, pump_fun, source::HealthHandle, EventSourceKind, SourceEvent, TokenEvent,
+};
+
+/// `getSignaturesForAddress` page size (the RPC maximum).
+const PAGE_SIZE: usize = 1_000;
+/// Signatures walked per gap at most; creates older than that are past any
+/// useful age by the time they are fetched.
+const MAX_SIGNATURES: usize = 2_000;
+const CONCURRENT_FETCHES: usize = 8;
+
+/// Recovers creates at or after `from_slot` in the background and feeds them
+/// into `tx`, flagged with their age.
+pub fn spawn(
+    rpc: Arc<RpcClient>,
+    program: Pubkey,
+    from_slot: u64,
+    source: EventSourceKind,
+    tx: EventSender,
+    health: HealthHandle,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        match recover_creates(&rpc, &program, from_slot, source).await {
+            Ok(events) => {
+                log::info!("Backfilled {} creates since slot {from_slot}", events.len());
+                for event in events {
+                    if tx.send(SourceEvent::Token(event)).is_err() {
+                        return;
+                    }
+                    health.event();
+                }
+            }
+            Err(err) => log::warn!("Backfill since slot {from_slot} failed: {err}"),
+        }
+    })
+}
+
+/// Fetches the creates that landed at or after `from_slot`, oldest first.
+/// Walks the mint-authority PDA's history rather than the program's, so
+/// every signature fetched is a launch.
+pub async fn recover_creates(
+    rpc: &RpcClient,
+    program: &Pubkey,
+    from_slot: u64,
+    source: EventSourceKind,
+) -> Result<Vec<TokenEvent>> {
+    let signatures = signatures_since(rpc, program, from_slot).await?;
+    let now = SystemTime::now();
+    let events: Vec<Vec<TokenEvent>> = stream::iter(signatures.into_iter().rev())
+        .map(|signature| async move {
+            let config = json!({
+                "encoding": "base64",
+                "commitment": "confirmed",
+                "maxSupportedTransactionVersion": 0
+            });
+            let params = json!([signature.to_string(), config]);
+            match rpc.send::<Value>(RpcRequest::GetTransaction, params).await {
+                Ok(transaction) => {
+                    creates_from_transaction(&transaction, program, signature, source, now)
+                }
+                Err(err) => {
+                    log::debug!("Failed to fetch {signature} for backfill: {err}");
+                    vec![]
+                }
+            }
+        })
+        .buffered(CONCURRENT_FETCHES)
+        .collect()
+        .await;
+    Ok(events.into_iter().flatten().collect())
+}
+
+/// Successful signatures of `program`'s mint authority at or after
+/// `from_slot`, newest first.
+async fn signatures_since(
+    rpc: &RpcClient,
+    program: &Pubkey,
+    from_slot: u64,
+) -> Result<Vec<Signature>> {
+    let address = pump_fun::mint_authority(program).to_string();
+    let mut signatures = Vec::new();
+    let mut before: Option<String> = None;
+
+    loop {
+        let mut config = json!({ "limit": PAGE_SIZE, "commitment": "confirmed" });
+        if let Some(before) = &before {
+            config["before"] = json!(before);
+        }
+        let page: Vec<Value> = rpc
+            .send(
+                RpcRequest::GetSignaturesForAddress,
+                json!([address, config]),
+            )
+            .await?;
+        let page_len = page.len();
+
+        for entry in page {
+            if entry.get("slot").and_then(Value::as_u64).unwrap_or(0) < from_slot {
+                return Ok(signatures);
+            }
+            before = entry
+                .get("signature")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if entry.get("err").is_some_and(|err| !err.is_null()) {
+                continue;
+            }
+            if let Some(signature) = before.as_deref().and_then(|s| Signature::from_str(s).ok()) {
+                signatures.push(signature);
+            }
+            if signatures.len() >= MAX_SIGNATURES {
+                log::warn!("Backfill capped at {MAX_SIGNATURES} creates since slot {from_slot}");
+                return Ok(signatures);
+            }
+        }
+        if page_len < PAGE_SIZE {
+            return Ok(signatures);
+        }
+    }
+}
+
+/// Decodes the creates in a `getTransaction` result, aged by its block time.
+fn creates_from_transaction(
+    transaction: &Value,
+    program: &Pubkey,
+    signature: Signature,
+    source: EventSourceKind,
+    now: SystemTime,
+) -> Vec<TokenEvent> {
+    let logs: Vec<&str> = transaction
+        .pointer("/meta/logMessages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    let slot = transaction.get("slot").and_then(Value::as_u64);
+    let age = transaction
+        .get("blockTime")
+        .and_then(Value::as_u64)
+        .and_then(|at| {
+            now.duration_since(UNIX_EPOCH + Duration::from_secs(at))
+                .ok()
+        })
+        .unwrap_or_default();
+
+    // Decoded with the trades so creates carry the developer's initial buy.
+    pump_fun::source_events_from_logs(&logs, program, source, slot, Some(signature))
+        .into_iter()
+        .filter_map(|event| match event {
+            SourceEvent::Token(event) => Some(TokenEvent {
+                backfill_age: Some(age),
+                ..event
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::pump_fun::PUMP_FUN_PROGRAM_ID;
+
+    const CREATE_TX: &str =
+        include_str!("../../../learning-examples/raw_create_tx_from_getTransaction.json");
+
+    #[test]
+    fn ages_recovered_creates_by_block_time() {
+        let response: Value = serde_json::from_str(CREATE_TX).unwrap();
+        let now = UNIX_EPOCH + Duration::from_secs(1_724_128_600 + 42);
+        let signature = Signature::new_unique();
+
+        let events = creates_from_transaction(
+            &response["result"],
+            &PUMP_FUN_PROGRAM_ID,
+            signature,
+            EventSourceKind::WebSocket,
+            now,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].slot, Some(284682639));
+        assert_eq!(events[0].signature, Some(signature));
+        assert_eq!(events[0].backfill_age, Some(Duration::from_secs(42)));
+
+        assert!(creates_from_transaction(
+            &Value::Null,
+            &PUMP_FUN_PROGRAM_ID,
+            signature,
+            EventSourceKind::WebSocket,
+            now
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,373 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_sdk::{bs58, pubkey::Pubkey, transaction::VersionedTransaction};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{
+    capture::Recorder,
+    dedup::EventSender,
+    launchlab, pump_fun,
+    source::{EventSource, Health, HealthHandle},
+    EventSourceKind, Platform, SourceEvent,
+};
+use crate::config::Config;
+
+pub struct BlockSource {
+    endpoint: String,
+    config: Arc<Config>,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+}
+
+impl BlockSource {
+    pub fn new(endpoint: String, config: Arc<Config>, recorder: Option<Recorder>) -> Self {
+        Self {
+            endpoint,
+            config,
+            health: HealthHandle::default(),
+            recorder,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for BlockSource {
+    fn name(&self) -> &str {
+        "block"
+    }
+
+    async fn run(&self, tx: EventSender) -> Result<()> {
+        run(
+            self.endpoint.clone(),
+            self.config.clone(),
+            tx,
+            self.health.clone(),
+            self.recorder.clone(),
+        )
+        .await
+    }
+
+    fn health(&self) -> Health {
+        self.health.snapshot()
+    }
+}
+
+pub async fn run(
+    ws_endpoint: String,
+    config: Arc<Config>,
+    tx: EventSender,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+) -> Result<()> {
+    log::info!("Starting block listener at {ws_endpoint}");
+    let mut backoff = Duration::from_millis(500);
+    let program = config.program_id()?;
+    let mut accounts = vec![program];
+    if config.tracks(Platform::LetsBonk) {
+        accounts.push(launchlab::LETS_BONK_PLATFORM_CONFIG);
+    }
+
+    loop {
+        match connect_async(&ws_endpoint).await {
+            Ok((mut socket, _)) => {
+                log::info!("Block subscription connected");
+                health.connected();
+                for (id, account) in (1..).zip(&accounts) {
+                    let subscribe_message = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "method": "blockSubscribe",
+                        "params": [
+                            { "mentionsAccountOrProgram": account.to_string() },
+                            {
+                                "commitment": "confirmed",
+                                "encoding": "base64",
+                                "showRewards": false,
+                                "transactionDetails": "full",
+                                "maxSupportedTransactionVersion": 0
+                            }
+                        ]
+                    })
+                    .to_string();
+
+                    let _ = socket
+                        .send(Message::text(subscribe_message))
+                        .await
+                        .map_err(|err| log::warn!("Failed to send subscribe message: {err}"));
+                }
+
+                while let Some(message) = socket.next().await {
+                    match message {
+                        Ok(Message::Text(text)) => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record_text(EventSourceKind::Block, &text);
+                            }
+                            for event in parse_block(&text, &program) {
+                                if tx.send(event).is_err() {
+                                    log::warn!("Receiver dropped, closing block listener");
+                                    return Ok(());
+                                }
+                                health.event();
+                            }
+                        }
+                        Ok(Message::Binary(_)) => {}
+                        Ok(Message::Pong(_)) | Ok(Message::Frame(_)) => {}
+                        Ok(Message::Ping(data)) => {
+                            let _ = socket.send(Message::Pong(data)).await;
+                        }
+                        Ok(Message::Close(frame)) => {
+                            log::warn!("Block subscription closed: {frame:?}");
+                            health.disconnected(format!("closed: {frame:?}"));
+                            break;
+                        }
+                        Err(err) => {
+                            log::warn!("Block subscription error: {err}");
+                            health.disconnected(&err);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                log::warn!("Block subscription connection failed: {err}");
+                health.disconnected(&err);
+            }
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff + Duration::from_millis(500)).min(Duration::from_secs(5));
+    }
+}
+
+/// Decodes pump.fun create and Let's Bonk initialize instructions and logged
+/// trades from every transaction in a `blockNotification` payload.
+pub fn parse_block(raw: &str, program: &Pubkey) -> Vec<SourceEvent> {
+    let Ok(json) = serde_json::from_str::<Value>(raw) else {
+        return vec![];
+    };
+    let Some(value) = json.pointer("/params/result/value") else {
+        return vec![];
+    };
+    let Some(slot) = value.get("slot").and_then(Value::as_u64) else {
+        return vec![];
+    };
+    let Some(transactions) = value
+        .pointer("/block/transactions")
+        .and_then(Value::as_array)
+    else {
+        return vec![];
+    };
+
+    transactions
+        .iter()
+        .flat_map(|entry| decode_transaction(entry, program, slot))
+        .collect()
+}
+
+/// Decodes one base64-encoded block transaction entry, resolving lookup-table
+/// accounts from `meta.loadedAddresses`.
+fn decode_transaction(entry: &Value, program: &Pubkey, slot: u64) -> Vec<SourceEvent> {
+    let meta = entry.get("meta").unwrap_or(&Value::Null);
+    if meta.get("err").is_some_and(|err| !err.is_null()) {
+        return vec![];
+    }
+    let Some(encoded) = entry.pointer("/transaction/0").and_then(Value::as_str) else {
+        return vec![];
+    };
+    let Ok(bytes) = STANDARD.decode(encoded) else {
+        return vec![];
+    };
+    let Ok(transaction) = bincode::deserialize::<VersionedTransaction>(&bytes) else {
+        return vec![];
+    };
+    let signature = transaction.signatures.first().copied();
+
+    let loaded = ["writable", "readonly"].into_iter().flat_map(|kind| {
+        meta.pointer(&format!("/loadedAddresses/{kind}"))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|key| Pubkey::from_str(key.as_str()?).ok())
+    });
+    let keys: Vec<Pubkey> = transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .copied()
+        .chain(loaded)
+        .collect();
+
+    let inner: Vec<(usize, Vec<u8>, Vec<u8>)> = meta
+        .get("innerInstructions")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|group| group.get("instructions")?.as_array())
+        .flatten()
+        .filter_map(|ix| {
+            let program_index = ix.get("programIdIndex")?.as_u64()? as usize;
+            let accounts = ix
+                .get("accounts")?
+                .as_array()?
+                .iter()
+                .map(|index| index.as_u64().map(|index| index as u8))
+                .collect::<Option<Vec<u8>>>()?;
+            let data = bs58::decode(ix.get("data")?.as_str()?).into_vec().ok()?;
+            Some((program_index, accounts, data))
+        })
+        .collect();
+
+    let top_level = transaction.message.instructions().iter().map(|ix| {
+        (
+            ix.program_id_index as usize,
+            ix.accounts.as_slice(),
+            ix.data.as_slice(),
+        )
+    });
+    let inner = inner
+        .iter()
+        .map(|(index, accounts, data)| (*index, accounts.as_slice(), data.as_slice()));
+
+    let logs: Vec<&str> = meta
+        .get("logMessages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+
+    let instructions: Vec<pump_fun::CompiledInstruction> = top_level.chain(inner).collect();
+    let creates = pump_fun::create_instructions(&keys, instructions.iter().copied(), program)
+        .into_iter()
+        .map(|ix| ix.into_token_event(EventSourceKind::Block, Some(slot), signature));
+    let launches = launchlab::token_events(
+        &keys,
+        instructions.iter().copied(),
+        &logs,
+        EventSourceKind::Block,
+        Some(slot),
+        signature,
+    );
+    let creates = creates.chain(launches).map(SourceEvent::Token);
+    let logged = pump_fun::logged_updates(
+        &logs,
+        program,
+        EventSourceKind::Block,
+        Some(slot),
+        signature,
+    );
+    let mut events: Vec<SourceEvent> = creates.chain(logged).collect();
+    pump_fun::attach_initial_buys(&mut events);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        instruction::{AccountMeta, Instruction},
+        message::Message as LegacyMessage,
+        transaction::Transaction,
+    };
+
+    use super::*;
+    use crate::events::{pump_fun::PUMP_FUN_PROGRAM_ID, TokenEvent};
+
+    const BLOCK_TX: &str = include_str!(
+        "../../../learning-examples/blockSubscribe-transactions/raw_create_tx_from_blockSubscribe.json"
+    );
+    const CREATE_TX: &str =
+        include_str!("../../../learning-examples/raw_create_tx_from_getTransaction.json");
+
+    fn block_notification(slot: u64, transactions: Vec<Value>) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "blockNotification",
+            "params": {
+                "result": {
+                    "context": { "slot": slot },
+                    "value": {
+                        "slot": slot,
+                        "block": { "blockhash": "11111111111111111111111111111111", "transactions": transactions },
+                        "err": null
+                    }
+                },
+                "subscription": 1
+            }
+        })
+        .to_string()
+    }
+
+    /// Re-encodes the `create` instruction from the getTransaction fixture as
+    /// a base64 block transaction entry.
+    fn create_entry() -> Value {
+        let fixture: Value = serde_json::from_str(CREATE_TX).unwrap();
+        let ix = &fixture["result"]["transaction"]["message"]["instructions"][3];
+        let pubkey = |value: &Value| Pubkey::from_str(value.as_str().unwrap()).unwrap();
+
+        let accounts: Vec<AccountMeta> = ix["accounts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|key| AccountMeta::new(pubkey(key), false))
+            .collect();
+        let payer = accounts[7].pubkey;
+        let instruction = Instruction {
+            program_id: pubkey(&ix["programId"]),
+            accounts,
+            data: bs58::decode(ix["data"].as_str().unwrap())
+                .into_vec()
+                .unwrap(),
+        };
+        let transaction =
+            Transaction::new_unsigned(LegacyMessage::new(&[instruction], Some(&payer)));
+        let encoded = STANDARD.encode(bincode::serialize(&transaction).unwrap());
+
+        json!({
+            "transaction": [encoded, "base64"],
+            "meta": { "err": null, "innerInstructions": [], "loadedAddresses": { "writable": [], "readonly": [] } },
+            "version": "legacy"
+        })
+    }
+
+    #[test]
+    fn decodes_create_from_block_notification() {
+        let fixture: Value = serde_json::from_str(BLOCK_TX).unwrap();
+        let raw = block_notification(300_000_000, vec![fixture, create_entry()]);
+
+        let events: Vec<TokenEvent> = parse_block(&raw, &PUMP_FUN_PROGRAM_ID)
+            .into_iter()
+            .filter_map(SourceEvent::into_token)
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].mint,
+            Pubkey::from_str("ER2N5eaDoC68kNhj7LyaScimzq7deaqxiw88rewvxaKp").unwrap()
+        );
+        assert_eq!(
+            events[0].developer,
+            Pubkey::from_str("Fswrw3tgQL597kCexxLEhft6a7Su4CDoqwRMqqj4BEp5").unwrap()
+        );
+        assert_eq!(events[0].slot, Some(300_000_000));
+        assert!(matches!(events[0].source, EventSourceKind::Block));
+    }
+
+    #[test]
+    fn decodes_trades_without_creates_from_buy_transactions() {
+        let fixture: Value = serde_json::from_str(BLOCK_TX).unwrap();
+        let raw = block_notification(1, vec![fixture]);
+        let events = parse_block(&raw, &PUMP_FUN_PROGRAM_ID);
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|event| matches!(
+            event,
+            SourceEvent::Trade(update) if update.slot == Some(1) && update.signature.is_some()
+        )));
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc as std_mpsc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prost::Message as _;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use super::{
+    block, copytrade,
+    dedup::EventSender,
+    laserstream,
+    laserstream::proto::geyser::SubscribeUpdate,
+    pumpportal,
+    source::{EventSource, Health, HealthHandle},
+    websocket, EventSourceKind, SourceEvent,
+};
+
+/// One raw message as a source received it. `data` is the WebSocket text
+/// frame, or the protobuf-encoded `SubscribeUpdate` in base64 for LaserStream.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CaptureRecord {
+    /// Receive time in microseconds since the Unix epoch.
+    pub at_us: u64,
+    pub source: EventSourceKind,
+    pub data: String,
+}
+
+impl CaptureRecord {
+    /// Decodes the record with the parser of the source that captured it.
+    pub fn events(&self, program: &Pubkey) -> Vec<SourceEvent> {
+        match self.source {
+            EventSourceKind::WebSocket => websocket::parse_events(&self.data, program),
+            EventSourceKind::Block => block::parse_block(&self.data, program),
+            EventSourceKind::PumpPortal => pumpportal::parse_event(&self.data)
+                .map(SourceEvent::Token)
+                .into_iter()
+                .collect(),
+            EventSourceKind::CopyTrade => copytrade::parse_copy_trades(&self.data, program)
+                .into_iter()
+                .map(SourceEvent::CopyTrade)
+                .collect(),
+            EventSourceKind::LaserStream => STANDARD
+                .decode(&self.data)
+                .ok()
+                .and_then(|bytes| SubscribeUpdate::decode(bytes.as_slice()).ok())
+                .map(|update| laserstream::parse_update(update, program))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Tees raw source messages into a JSONL capture file. Writes happen on a
+/// dedicated thread so listeners never block on disk.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    tx: std_mpsc::Sender<CaptureRecord>,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create capture file {}", path.display()))?;
+        let (tx, rx) = std_mpsc::channel::<CaptureRecord>();
+
+        std::thread::spawn(move || {
+            let mut writer = BufWriter::new(file);
+            while let Ok(record) = rx.recv() {
+                let mut pending = Some(record);
+                while let Some(record) = pending {
+                    let written = serde_json::to_writer(&mut writer, &record)
+                        .map_err(std::io::Error::from)
+                        .and_then(|()| writer.write_all(b"\n"));
+                    if let Err(err) = written {
+                        log::warn!("Failed to write capture record: {err}");
+                    }
+                    pending = rx.try_recv().ok();
+                }
+                let _ = writer.flush();
+            }
+        });
+
+        log::info!("Recording raw source messages to {}", path.display());
+        Ok(Self { tx })
+    }
+
+    pub fn record_text(&self, source: EventSourceKind, text: &str) {
+        self.send(source, text.to_string());
+    }
+
+    pub fn record_update(&self, update: &SubscribeUpdate) {
+        self.send(
+            EventSourceKind::LaserStream,
+            STANDARD.encode(update.encode_to_vec()),
+        );
+    }
+
+    fn send(&self, source: EventSourceKind, data: String) {
+        let _ = self.tx.send(CaptureRecord {
+            at_us: unix_micros(SystemTime::now()),
+            source,
+            data,
+        });
+    }
+}
+
+fn unix_micros(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+pub fn read_capture(path: impl AsRef<Path>) -> Result<Vec<CaptureRecord>> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open capture file {}", path.display()))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(|(index, line)| {
+            serde_json::from_str(&line?)
+                .map_err(|err| anyhow!("{}:{}: {err}", path.display(), index + 1))
+        })
+        .collect()
+}
+
+/// Feeds a capture file back through the normal parsers. `speed` scales the
+/// original gaps between messages; `0` replays as fast as possible.
+pub struct ReplaySource {
+    path: PathBuf,
+    program: Pubkey,
+    speed: f64,
+    health: HealthHandle,
+}
+
+impl ReplaySource {
+    pub fn new(path: impl Into<PathBuf>, program: Pubkey, speed: f64) -> Self {
+        Self {
+            path: path.into(),
+            program,
+            speed,
+            health: HealthHandle::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for ReplaySource {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    async fn run(&self, tx: EventSender) -> Result<()> {
+        let records = read_capture(&self.path)?;
+        log::info!(
+            "Replaying {} records from {} at {}x",
+            records.len(),
+            self.path.display(),
+            self.speed
+        );
+        self.health.connected();
+
+        let started = tokio::time::Instant::now();
+        let first_at = records.first().map_or(0, |record| record.at_us);
+        for record in &records {
+            if self.speed > 0.0 {
+                let offset = Duration::from_micros(record.at_us.saturating_sub(first_at));
+                tokio::time::sleep_until(started + offset.div_f64(self.speed)).await;
+            }
+            for event in record.events(&self.program) {
+                if tx.send(event).is_err() {
+                    return Ok(());
+                }
+                self.health.event();
+            }
+        }
+
+        log::info!("Replay of {} finished", self.path.display());
+        self.health.disconnected("replay finished");
+        Ok(())
+    }
+
+    fn health(&self) -> Health {
+        self.health.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::events::pump_fun::PUMP_FUN_PROGRAM_ID;
+
+    const CREATE_TX: &str =
+        include_str!("../../../learning-examples/raw_create_tx_from_getTransaction.json");
+
+    fn logs_notification() -> String {
+        let tx: Value = serde_json::from_str(CREATE_TX).unwrap();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "logsNotification",
+            "params": {
+                "result": {
+                    "context": { "slot": tx["result"]["slot"] },
+                    "value": {
+                        "signature": tx["result"]["transaction"]["signatures"][0],
+                        "err": null,
+                        "logs": tx["result"]["meta"]["logMessages"],
+                    }
+                },
+                "subscription": 1
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_messages() {
+        let path = std::env::temp_dir().join(format!(
+            "capture-{}-{}.jsonl",
+            std::process::id(),
+            unix_micros(SystemTime::now())
+        ));
+        let recorder = Recorder::create(&path).unwrap();
+        recorder.record_text(
+            EventSourceKind::WebSocket,
+            r#"{"jsonrpc":"2.0","result":1,"id":1}"#,
+        );
+        recorder.record_text(EventSourceKind::WebSocket, &logs_notification());
+        drop(recorder);
+
+        let mut records = Vec::new();
+        for _ in 0..50 {
+            records = read_capture(&path).unwrap_or_default();
+            if records.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(records.len(), 2);
+
+        let (tx, mut rx) = EventSender::for_test();
+        let source = ReplaySource::new(&path, PUMP_FUN_PROGRAM_ID, 0.0);
+        source.run(tx).await.unwrap();
+        let event = rx.recv().await.and_then(SourceEvent::into_token).unwrap();
+        assert_eq!(event.slot, Some(284682639));
+        assert_eq!(event.source, EventSourceKind::WebSocket);
+        assert!(matches!(rx.recv().await, Some(SourceEvent::Trade(_))));
+        assert!(rx.recv().await.is_none());
+        assert_eq!(source.health().events, 2);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
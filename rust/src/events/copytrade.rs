@@ -0,0 +1,281 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{
+    capture::Recorder,
+    dedup::EventSender,
+    pump_fun, pump_swap,
+    source::{EventSource, Health, HealthHandle},
+    websocket::LogsNotification,
+    CopyTradeEvent, EventSourceKind, LeaderTrade, SourceEvent,
+};
+
+/// Follows leader wallets with one `logsSubscribe` per wallet and emits their
+/// pump.fun and PumpSwap trades.
+pub struct CopyTradeSource {
+    endpoint: String,
+    program: Pubkey,
+    leaders: Vec<Pubkey>,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+}
+
+impl CopyTradeSource {
+    pub fn new(
+        endpoint: String,
+        program: Pubkey,
+        leaders: Vec<Pubkey>,
+        recorder: Option<Recorder>,
+    ) -> Self {
+        Self {
+            endpoint,
+            program,
+            leaders,
+            health: HealthHandle::default(),
+            recorder,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for CopyTradeSource {
+    fn name(&self) -> &str {
+        "copytrade"
+    }
+
+    async fn run(&self, tx: EventSender) -> Result<()> {
+        run(
+            self.endpoint.clone(),
+            self.program,
+            self.leaders.clone(),
+            tx,
+            self.health.clone(),
+            self.recorder.clone(),
+        )
+        .await
+    }
+
+    fn health(&self) -> Health {
+        self.health.snapshot()
+    }
+
+    /// Leader trades only; creates come from the other sources.
+    fn reports_slots(&self) -> bool {
+        false
+    }
+}
+
+pub async fn run(
+    ws_endpoint: String,
+    program: Pubkey,
+    leaders: Vec<Pubkey>,
+    tx: EventSender,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+) -> Result<()> {
+    log::info!(
+        "Starting copy-trade listener for {} wallets at {ws_endpoint}",
+        leaders.len()
+    );
+    let tracked: HashSet<Pubkey> = leaders.iter().copied().collect();
+    let mut backoff = Duration::from_millis(500);
+
+    loop {
+        match connect_async(&ws_endpoint).await {
+            Ok((mut socket, _)) => {
+                log::info!("Copy-trade WebSocket connected");
+                health.connected();
+                // `mentions` takes a single address per subscription.
+                for (id, leader) in (1..).zip(&leaders) {
+                    let subscribe_message = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "method": "logsSubscribe",
+                        "params": [
+                            { "mentions": [leader.to_string()] },
+                            { "commitment": "processed" }
+                        ]
+                    })
+                    .to_string();
+                    let _ = socket
+                        .send(Message::text(subscribe_message))
+                        .await
+                        .map_err(|err| log::warn!("Failed to subscribe to {leader}: {err}"));
+                }
+
+                while let Some(message) = socket.next().await {
+                    match message {
+                        Ok(Message::Text(text)) => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record_text(EventSourceKind::CopyTrade, &text);
+                            }
+                            let events = parse_copy_trades(&text, &program)
+                                .into_iter()
+                                .filter(|event| tracked.contains(&event.leader));
+                            for event in events {
+                                if tx.send(SourceEvent::CopyTrade(event)).is_err() {
+                                    log::warn!("Receiver dropped, closing copy-trade listener");
+                                    return Ok(());
+                                }
+                                health.event();
+                            }
+                        }
+                        Ok(Message::Binary(_)) => {}
+                        Ok(Message::Pong(_)) | Ok(Message::Frame(_)) => {}
+                        Ok(Message::Ping(data)) => {
+                            let _ = socket.send(Message::Pong(data)).await;
+                        }
+                        Ok(Message::Close(frame)) => {
+                            log::warn!("Copy-trade WebSocket closed: {frame:?}");
+                            health.disconnected(format!("closed: {frame:?}"));
+                            break;
+                        }
+                        Err(err) => {
+                            log::warn!("Copy-trade WebSocket error: {err}");
+                            health.disconnected(&err);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                log::warn!("Copy-trade WebSocket connection failed: {err}");
+                health.disconnected(&err);
+            }
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff + Duration::from_millis(500)).min(Duration::from_secs(5));
+    }
+}
+
+/// Decodes every pump.fun and PumpSwap trade in a `logsNotification`, with
+/// the trading wallet as leader. The listener keeps only tracked wallets.
+pub fn parse_copy_trades(raw: &str, program: &Pubkey) -> Vec<CopyTradeEvent> {
+    let Some(notification) = LogsNotification::parse(raw) else {
+        return vec![];
+    };
+    copy_trades_from_logs(
+        &notification.logs,
+        program,
+        EventSourceKind::CopyTrade,
+        notification.slot,
+        notification.signature,
+    )
+}
+
+pub fn copy_trades_from_logs<S: AsRef<str>>(
+    logs: &[S],
+    program: &Pubkey,
+    source: EventSourceKind,
+    slot: Option<u64>,
+    signature: Option<Signature>,
+) -> Vec<CopyTradeEvent> {
+    let curve = pump_fun::trade_events_from_logs(logs, program)
+        .into_iter()
+        .map(|trade| (trade.user, LeaderTrade::Curve(trade)));
+    let swaps = pump_fun::program_data(logs, &pump_swap::PUMP_SWAP_PROGRAM_ID)
+        .filter_map(|data| pump_swap::SwapEvent::decode(&data))
+        .map(|swap| (swap.user, LeaderTrade::Swap(swap)));
+
+    curve
+        .chain(swaps)
+        .zip(0..)
+        .map(|((leader, trade), ordinal)| CopyTradeEvent {
+            leader,
+            trade,
+            source,
+            slot,
+            signature,
+            ordinal,
+            received_at: SystemTime::now(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::events::pump_fun::PUMP_FUN_PROGRAM_ID;
+
+    const BUY_TX: &str =
+        include_str!("../../../learning-examples/raw_buy_tx_from_getTransaction.json");
+
+    fn swap_event(pool: &Pubkey, user: &Pubkey) -> Vec<u8> {
+        let mut data = pump_swap::SELL_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&5_000_000u64.to_le_bytes());
+        data.extend_from_slice(&[0; 11 * 8]);
+        data.extend_from_slice(&2_500_000u64.to_le_bytes());
+        data.extend_from_slice(pool.as_ref());
+        data.extend_from_slice(user.as_ref());
+        data.extend_from_slice(&[0; 5 * 32 + 2 * 8]);
+        data
+    }
+
+    #[test]
+    fn decodes_curve_and_swap_trades() {
+        let tx: Value = serde_json::from_str(BUY_TX).unwrap();
+        let mut logs: Vec<String> =
+            serde_json::from_value(tx["result"]["meta"]["logMessages"].clone()).unwrap();
+        let pool = Pubkey::new_unique();
+        let leader = Pubkey::new_unique();
+        let pump_swap = pump_swap::PUMP_SWAP_PROGRAM_ID.to_string();
+        logs.extend([
+            format!("Program {pump_swap} invoke [1]"),
+            format!(
+                "Program data: {}",
+                STANDARD.encode(swap_event(&pool, &leader))
+            ),
+            format!("Program {pump_swap} success"),
+        ]);
+        let raw = json!({
+            "jsonrpc": "2.0",
+            "method": "logsNotification",
+            "params": {
+                "result": {
+                    "context": { "slot": 42 },
+                    "value": {
+                        "signature": tx["result"]["transaction"]["signatures"][0],
+                        "err": null,
+                        "logs": logs,
+                    }
+                },
+                "subscription": 1
+            }
+        })
+        .to_string();
+
+        let events = parse_copy_trades(&raw, &PUMP_FUN_PROGRAM_ID);
+        assert_eq!(events.len(), 3);
+        assert!(events[0].is_buy());
+        assert_eq!(events[0].sol_amount(), 24_080_282);
+        assert!(
+            matches!(&events[0].trade, LeaderTrade::Curve(trade) if trade.user == events[0].leader)
+        );
+
+        let swap = &events[2];
+        assert_eq!(swap.leader, leader);
+        assert_eq!(swap.ordinal, 2);
+        assert_eq!(swap.slot, Some(42));
+        assert!(!swap.is_buy());
+        assert_eq!(
+            (swap.token_amount(), swap.sol_amount()),
+            (5_000_000, 2_500_000)
+        );
+        assert!(matches!(&swap.trade, LeaderTrade::Swap(event) if event.pool == pool));
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use parking_lot::Mutex;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use super::{
+    queue::{QueueClosed, QueueSender},
+    EventSourceKind, SourceEvent, TokenEvent,
+};
+
+/// How long a mint is remembered after its first arrival. Slower feeds trail
+/// by milliseconds, so this only bounds memory.
+pub const DEDUP_WINDOW: Duration = Duration::from_secs(120);
+
+/// Race results for one source, counted only over mints that at least two
+/// sources delivered.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceRace {
+    pub races: u64,
+    pub wins: u64,
+    /// Sum of the winning margin over every later arrival, in microseconds.
+    pub total_lead_us: u128,
+    pub leads: u64,
+}
+
+impl SourceRace {
+    pub fn win_rate(&self) -> f64 {
+        if self.races == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.races as f64
+    }
+
+    pub fn mean_lead_us(&self) -> u128 {
+        if self.leads == 0 {
+            return 0;
+        }
+        self.total_lead_us / self.leads as u128
+    }
+}
+
+/// Per-source win statistics shared between the fan-in task and readers.
+#[derive(Clone, Debug, Default)]
+pub struct RaceStats {
+    inner: Arc<Mutex<HashMap<EventSourceKind, SourceRace>>>,
+}
+
+impl RaceStats {
+    pub fn snapshot(&self) -> Vec<(EventSourceKind, SourceRace)> {
+        let mut sources: Vec<_> = self
+            .inner
+            .lock()
+            .iter()
+            .map(|(source, race)| (*source, race.clone()))
+            .collect();
+        sources.sort_by_key(|(source, _)| format!("{source:?}"));
+        sources
+    }
+
+    /// One-line summary for periodic logging.
+    pub fn summary(&self) -> String {
+        self.snapshot()
+            .iter()
+            .map(|(source, race)| {
+                format!(
+                    "{source:?} won {}/{} ({:.1}%, mean lead {}us)",
+                    race.wins,
+                    race.races,
+                    race.win_rate() * 100.0,
+                    race.mean_lead_us()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn record(
+        &self,
+        winner: EventSourceKind,
+        loser: EventSourceKind,
+        first_race: bool,
+        lead: Duration,
+    ) {
+        let mut inner = self.inner.lock();
+        let entry = inner.entry(winner).or_default();
+        if first_race {
+            entry.races += 1;
+            entry.wins += 1;
+        }
+        entry.total_lead_us += lead.as_micros();
+        entry.leads += 1;
+        inner.entry(loser).or_default().races += 1;
+    }
+}
+
+struct Arrival {
+    source: EventSourceKind,
+    at: SystemTime,
+    seen: Vec<EventSourceKind>,
+}
+
+/// Which per-transaction event list an ordinal indexes into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Logged {
+    Trade,
+    Migration,
+    CopyTrade,
+}
+
+type LoggedKey = (Logged, Signature, u32);
+
+/// Drops repeat deliveries of the same create, keyed by mint since one
+/// transaction can create several, and scores which source delivered it first. Trades and migrations are
+/// keyed by signature and ordinal, as are copy trades.
+pub struct Dedup {
+    window: Duration,
+    mints: HashMap<Pubkey, Arrival>,
+    order: VecDeque<(SystemTime, Pubkey)>,
+    logged: HashSet<LoggedKey>,
+    logged_order: VecDeque<(SystemTime, LoggedKey)>,
+    stats: RaceStats,
+}
+
+impl Dedup {
+    pub fn new(window: Duration, stats: RaceStats) -> Self {
+        Self {
+            window,
+            mints: HashMap::new(),
+            order: VecDeque::new(),
+            logged: HashSet::new(),
+            logged_order: VecDeque::new(),
+            stats,
+        }
+    }
+
+    /// Returns `true` when `event` is the first arrival and should be
+    /// forwarded.
+    pub fn observe(&mut self, event: &SourceEvent) -> bool {
+        match event {
+            SourceEvent::Token(event) => self.observe_token(event),
+            SourceEvent::Trade(update) => self.observe_logged(
+                Logged::Trade,
+                update.signature,
+                update.ordinal,
+                update.received_at,
+            ),
+            SourceEvent::Migration(event) => self.observe_logged(
+                Logged::Migration,
+                event.signature,
+                event.ordinal,
+                event.received_at,
+            ),
+            SourceEvent::CopyTrade(event) => self.observe_logged(
+                Logged::CopyTrade,
+                event.signature,
+                event.ordinal,
+                event.received_at,
+            ),
+        }
+    }
+
+    fn observe_logged(
+        &mut self,
+        kind: Logged,
+        signature: Option<Signature>,
+        ordinal: u32,
+        received_at: SystemTime,
+    ) -> bool {
+        let Some(signature) = signature else {
+            return true;
+        };
+        self.evict(received_at);
+        let key = (kind, signature, ordinal);
+        if !self.logged.insert(key) {
+            return false;
+        }
+        self.logged_order.push_back((received_at, key));
+        true
+    }
+
+    fn observe_token(&mut self, event: &TokenEvent) -> bool {
+        self.evict(event.received_at);
+
+        let Some(arrival) = self.mints.get_mut(&event.mint) else {
+            self.mints.insert(
+                event.mint,
+                Arrival {
+                    source: event.source,
+                    at: event.received_at,
+                    seen: vec![event.source],
+                },
+            );
+            self.order.push_back((event.received_at, event.mint));
+            return true;
+        };
+
+        // Backfilled copies arrive late by design and would skew the race.
+        if event.backfill_age.is_none() && !arrival.seen.contains(&event.source) {
+            let first_race = arrival.seen.len() == 1;
+            arrival.seen.push(event.source);
+            let lead = event
+                .received_at
+                .duration_since(arrival.at)
+                .unwrap_or_default();
+            log::debug!(
+                "{:?} beat {:?} by {}us for {}",
+                arrival.source,
+                event.source,
+                lead.as_micros(),
+                event.mint
+            );
+            self.stats
+                .record(arrival.source, event.source, first_race, lead);
+        }
+        false
+    }
+
+    fn evict(&mut self, now: SystemTime) {
+        while let Some((at, mint)) = self.order.front().copied() {
+            if now.duration_since(at).unwrap_or_default() < self.window {
+                break;
+            }
+            self.order.pop_front();
+            self.mints.remove(&mint);
+        }
+        while let Some((at, key)) = self.logged_order.front().copied() {
+            if now.duration_since(at).unwrap_or_default() < self.window {
+                break;
+            }
+            self.logged_order.pop_front();
+            self.logged.remove(&key);
+        }
+    }
+}
+
+/// The fan-in stage every source sends into. Each event is deduplicated as
+/// it's sent and only the first arrival of each create, trade and migration
+/// goes on, straight into the bounded pipeline queue, so a flood sheds
+/// there instead of piling up in between.
+#[derive(Clone)]
+pub struct EventSender {
+    dedup: Arc<Mutex<Dedup>>,
+    queue: QueueSender,
+}
+
+impl EventSender {
+    pub fn new(queue: QueueSender, stats: RaceStats) -> Self {
+        Self {
+            dedup: Arc::new(Mutex::new(Dedup::new(DEDUP_WINDOW, stats))),
+            queue,
+        }
+    }
+
+    /// Forwards `event` if it's a first arrival. Fails once the pipeline's
+    /// receiver is gone.
+    pub fn send(&self, event: SourceEvent) -> Result<(), QueueClosed> {
+        if self.dedup.lock().observe(&event) {
+            self.queue.send(event)
+        } else if self.queue.is_closed() {
+            Err(QueueClosed)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.queue.is_closed()
+    }
+}
+
+#[cfg(test)]
+impl EventSender {
+    /// A sender into a default-sized queue, and the queue's receiver.
+    pub fn for_test() -> (Self, super::queue::QueueReceiver) {
+        let (tx, rx) = super::queue::channel(&Default::default(), Default::default());
+        (Self::new(tx, RaceStats::default()), rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{pump_fun, TradeUpdate};
+
+    fn event(
+        mint: Pubkey,
+        signature: Option<Signature>,
+        source: EventSourceKind,
+        at: SystemTime,
+    ) -> SourceEvent {
+        SourceEvent::Token(TokenEvent {
+            source,
+            signature,
+            received_at: at,
+            ..TokenEvent::for_test(mint, Pubkey::new_unique())
+        })
+    }
+
+    #[test]
+    fn forwards_first_arrival_and_scores_race() {
+        let stats = RaceStats::default();
+        let mut dedup = Dedup::new(DEDUP_WINDOW, stats.clone());
+        let mint = Pubkey::new_unique();
+        let t0 = SystemTime::now();
+
+        assert!(dedup.observe(&event(mint, None, EventSourceKind::LaserStream, t0)));
+        assert!(!dedup.observe(&event(
+            mint,
+            None,
+            EventSourceKind::WebSocket,
+            t0 + Duration::from_micros(1_500)
+        )));
+        assert!(!dedup.observe(&event(
+            mint,
+            None,
+            EventSourceKind::WebSocket,
+            t0 + Duration::from_micros(1_600)
+        )));
+
+        let snapshot: HashMap<_, _> = stats.snapshot().into_iter().collect();
+        let laserstream = &snapshot[&EventSourceKind::LaserStream];
+        assert_eq!((laserstream.races, laserstream.wins), (1, 1));
+        assert_eq!(laserstream.mean_lead_us(), 1_500);
+        let websocket = &snapshot[&EventSourceKind::WebSocket];
+        assert_eq!((websocket.races, websocket.wins), (1, 0));
+        assert_eq!(websocket.win_rate(), 0.0);
+    }
+
+    #[test]
+    fn dedupes_by_mint_and_expires_after_window() {
+        let mut dedup = Dedup::new(Duration::from_secs(1), RaceStats::default());
+        let mint = Pubkey::new_unique();
+        let signature = Signature::new_unique();
+        let t0 = SystemTime::now();
+
+        assert!(dedup.observe(&event(mint, Some(signature), EventSourceKind::Block, t0)));
+        assert!(!dedup.observe(&event(
+            mint,
+            Some(signature),
+            EventSourceKind::LaserStream,
+            t0
+        )));
+        // A second create in the same transaction is its own launch.
+        assert!(dedup.observe(&event(
+            Pubkey::new_unique(),
+            Some(signature),
+            EventSourceKind::LaserStream,
+            t0
+        )));
+        assert!(dedup.observe(&event(
+            mint,
+            Some(signature),
+            EventSourceKind::WebSocket,
+            t0 + Duration::from_secs(2)
+        )));
+    }
+
+    #[test]
+    fn dedupes_trades_by_signature_and_ordinal() {
+        let mut dedup = Dedup::new(DEDUP_WINDOW, RaceStats::default());
+        let signature = Signature::new_unique();
+        let trade = |ordinal, source| {
+            SourceEvent::Trade(TradeUpdate {
+                trade: pump_fun::TradeEvent::for_test(
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    true,
+                ),
+                source,
+                slot: None,
+                signature: Some(signature),
+                ordinal,
+                received_at: SystemTime::now(),
+            })
+        };
+
+        assert!(dedup.observe(&trade(0, EventSourceKind::LaserStream)));
+        assert!(dedup.observe(&trade(1, EventSourceKind::LaserStream)));
+        assert!(!dedup.observe(&trade(0, EventSourceKind::WebSocket)));
+    }
+}
@@ -1,27 +1,541 @@
-use std::time::Duration;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use anyhow::Result;
-use tokio::sync::mpsc::UnboundedSender;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use hyper::client::HttpConnector;
+use hyper_tls::HttpsConnector;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    metadata::AsciiMetadataValue, service::Interceptor, transport::Channel, Request, Status,
+};
 
-use super::TokenEvent;
+use super::{
+    capture::Recorder,
+    dedup::EventSender,
+    launchlab,
+    pump_fun::{self, CompiledInstruction},
+    source::{EventSource, Health, HealthHandle},
+    EventSourceKind, Platform, SourceEvent,
+};
+use crate::config::Config;
 
-pub async fn run(endpoint: String, _tx: UnboundedSender<TokenEvent>) -> Result<()> {
+pub mod proto {
+    #![allow(dead_code, clippy::all)]
+
+    pub mod geyser {
+        tonic::include_proto!("geyser");
+    }
+
+    pub mod solana {
+        pub mod storage {
+            pub mod confirmed_block {
+                tonic::include_proto!("solana.storage.confirmed_block");
+            }
+        }
+    }
+}
+
+use proto::geyser::{
+    geyser_client::GeyserClient, subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdate,
+    SubscribeUpdateTransactionInfo,
+};
+
+pub struct LaserStreamSource {
+    endpoint: String,
+    config: Arc<Config>,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+}
+
+impl LaserStreamSource {
+    pub fn new(endpoint: String, config: Arc<Config>, recorder: Option<Recorder>) -> Self {
+        Self {
+            endpoint,
+            config,
+            health: HealthHandle::default(),
+            recorder,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for LaserStreamSource {
+    fn name(&self) -> &str {
+        "laserstream"
+    }
+
+    async fn run(&self, tx: EventSender) -> Result<()> {
+        run(
+            self.endpoint.clone(),
+            self.config.clone(),
+            tx,
+            self.health.clone(),
+            self.recorder.clone(),
+        )
+        .await
+    }
+
+    fn health(&self) -> Health {
+        self.health.snapshot()
+    }
+}
+
+pub async fn run(
+    endpoint: String,
+    config: Arc<Config>,
+    tx: EventSender,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+) -> Result<()> {
     log::info!("Starting LaserStream listener at {endpoint}");
+    let program = config.program_id()?;
+    let lets_bonk = config.tracks(Platform::LetsBonk);
+    let x_token = config
+        .endpoints
+        .laserstream_x_token
+        .as_deref()
+        .map(AsciiMetadataValue::try_from)
+        .transpose()
+        .map_err(|err| anyhow!("Invalid LaserStream x-token: {err}"))?;
     let mut backoff = Duration::from_millis(250);
+
     loop {
-        match tonic::transport::Channel::from_shared(endpoint.clone())?
-            .connect()
-            .await
+        match subscribe(
+            &endpoint,
+            x_token.clone(),
+            &program,
+            lets_bonk,
+            &tx,
+            &health,
+            recorder.as_ref(),
+        )
+        .await
         {
-            Ok(_channel) => {
-                log::info!("Connected to LaserStream (placeholder parser not wired)");
-                tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(true) => {
+                log::warn!("Receiver dropped, closing LaserStream listener");
+                return Ok(());
+            }
+            Ok(false) => {
+                log::warn!("LaserStream stream ended");
+                health.disconnected("stream ended");
+                backoff = Duration::from_millis(250);
             }
             Err(err) => {
                 log::warn!("LaserStream connection failed: {err}");
-                tokio::time::sleep(backoff).await;
-                backoff = (backoff + Duration::from_millis(250)).min(Duration::from_secs(5));
+                health.disconnected(&err);
+            }
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff + Duration::from_millis(250)).min(Duration::from_secs(5));
+    }
+}
+
+/// Runs one subscription until the stream ends. Returns `Ok(true)` when the
+/// event receiver is gone and the listener should stop.
+async fn subscribe(
+    endpoint: &str,
+    x_token: Option<AsciiMetadataValue>,
+    program: &Pubkey,
+    lets_bonk: bool,
+    tx: &EventSender,
+    health: &HealthHandle,
+    recorder: Option<&Recorder>,
+) -> Result<bool> {
+    let channel = connect(endpoint).await?;
+    let mut client = GeyserClient::with_interceptor(channel, XToken(x_token));
+
+    let (request_tx, request_rx) = mpsc::channel(4);
+    request_tx
+        .send(subscribe_request(program, lets_bonk))
+        .await?;
+    let mut stream = client
+        .subscribe(ReceiverStream::new(request_rx))
+        .await?
+        .into_inner();
+    log::info!("Connected to LaserStream");
+    health.connected();
+
+    while let Some(update) = stream.next().await {
+        let update = update?;
+        if matches!(update.update_oneof, Some(UpdateOneof::Ping(_))) {
+            let ping = SubscribeRequest {
+                ping: Some(SubscribeRequestPing { id: 1 }),
+                ..Default::default()
+            };
+            let _ = request_tx.send(ping).await;
+            continue;
+        }
+        if let Some(recorder) = recorder {
+            recorder.record_update(&update);
+        }
+
+        for event in parse_update(update, program) {
+            if tx.send(event).is_err() {
+                return Ok(true);
+            }
+            health.event();
+        }
+    }
+
+    Ok(false)
+}
+
+/// Attaches the Yellowstone `x-token` header to every request.
+struct XToken(Option<AsciiMetadataValue>);
+
+impl Interceptor for XToken {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = &self.0 {
+            req.metadata_mut().insert("x-token", token.clone());
+        }
+        Ok(req)
+    }
+}
+
+async fn connect(endpoint: &str) -> Result<Channel> {
+    let builder = Channel::from_shared(endpoint.to_string())?
+        .tcp_nodelay(true)
+        .http2_keep_alive_interval(Duration::from_secs(10))
+        .keep_alive_while_idle(true);
+
+    if !endpoint.starts_with("https://") {
+        return Ok(builder.connect().await?);
+    }
+
+    // tonic's rustls stack conflicts with the solana crates, so TLS goes
+    // through native-tls with h2 negotiated via ALPN.
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    http.set_nodelay(true);
+    let tls = native_tls::TlsConnector::builder()
+        .request_alpns(&["h2"])
+        .build()?;
+    let connector = HttpsConnector::from((http, tls.into()));
+    Ok(builder.connect_with_connector(connector).await?)
+}
+
+fn subscribe_request(program: &Pubkey, lets_bonk: bool) -> SubscribeRequest {
+    let filter = |account: &Pubkey| SubscribeRequestFilterTransactions {
+        vote: Some(false),
+        failed: Some(false),
+        account_include: vec![account.to_string()],
+        ..Default::default()
+    };
+    let mut transactions = HashMap::from([("pump_fun".to_string(), filter(program))]);
+    if lets_bonk {
+        transactions.insert(
+            "lets_bonk".to_string(),
+            filter(&launchlab::LETS_BONK_PLATFORM_CONFIG),
+        );
+    }
+    SubscribeRequest {
+        transactions,
+        commitment: Some(CommitmentLevel::Processed as i32),
+        ..Default::default()
+    }
+}
+
+/// Decodes every pump.fun `create`/`create_v2` and Let's Bonk initialize
+/// instruction in a transaction update, including ones invoked through CPI,
+/// followed by its logged trades.
+pub fn parse_update(update: SubscribeUpdate, program: &Pubkey) -> Vec<SourceEvent> {
+    let Some(UpdateOneof::Transaction(update)) = update.update_oneof else {
+        return vec![];
+    };
+    let slot = update.slot;
+    let Some(info) = update.transaction else {
+        return vec![];
+    };
+    let Some(meta) = info.meta.as_ref().filter(|meta| meta.err.is_none()) else {
+        return vec![];
+    };
+
+    let signature = Signature::try_from(info.signature.as_slice()).ok();
+    let source = EventSourceKind::LaserStream;
+    let logs = meta.log_messages.as_slice();
+
+    let mut events = Vec::new();
+    if let Some((keys, instructions)) = compiled_instructions(&info) {
+        let creates = pump_fun::create_instructions(&keys, instructions.iter().copied(), program)
+            .into_iter()
+            .map(|ix| ix.into_token_event(source, Some(slot), signature));
+        let launches = launchlab::token_events(
+            &keys,
+            instructions.iter().copied(),
+            logs,
+            source,
+            Some(slot),
+            signature,
+        );
+        events.extend(creates.chain(launches).map(SourceEvent::Token));
+    }
+    events.extend(pump_fun::logged_updates(
+        logs,
+        program,
+        source,
+        Some(slot),
+        signature,
+    ));
+    pump_fun::attach_initial_buys(&mut events);
+    events
+}
+
+/// A transaction's full (static + loaded) account keys and its top-level and
+/// inner instructions.
+fn compiled_instructions(
+    info: &SubscribeUpdateTransactionInfo,
+) -> Option<(Vec<Pubkey>, Vec<CompiledInstruction<'_>>)> {
+    let meta = info.meta.as_ref()?;
+    let message = info.transaction.as_ref()?.message.as_ref()?;
+
+    let keys: Vec<Pubkey> = message
+        .account_keys
+        .iter()
+        .chain(&meta.loaded_writable_addresses)
+        .chain(&meta.loaded_readonly_addresses)
+        .filter_map(|key| Pubkey::try_from(key.as_slice()).ok())
+        .collect();
+
+    let top_level = message.instructions.iter().map(|ix| {
+        (
+            ix.program_id_index as usize,
+            ix.accounts.as_slice(),
+            ix.data.as_slice(),
+        )
+    });
+    let inner = meta
+        .inner_instructions
+        .iter()
+        .flat_map(|inner| &inner.instructions)
+        .map(|ix| {
+            (
+                ix.program_id_index as usize,
+                ix.accounts.as_slice(),
+                ix.data.as_slice(),
+            )
+        });
+
+    Some((keys, top_level.chain(inner).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, pin::Pin};
+
+    use futures::Stream;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{transport::Server, Response, Streaming};
+
+    use super::proto::geyser::{
+        geyser_server::{Geyser, GeyserServer},
+        GetBlockHeightRequest, GetBlockHeightResponse, GetLatestBlockhashRequest,
+        GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest,
+        GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
+        PongResponse, SubscribeUpdateTransaction,
+    };
+    use super::proto::solana::storage::confirmed_block::{
+        CompiledInstruction, Message, Transaction, TransactionStatusMeta,
+    };
+    use super::*;
+    use crate::events::{
+        pump_fun::{CREATE_DISCRIMINATOR, PUMP_FUN_PROGRAM_ID},
+        TokenEvent,
+    };
+
+    const X_TOKEN: &str = "test-token";
+
+    struct StandIn {
+        update: SubscribeUpdate,
+    }
+
+    type UpdateStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+    #[tonic::async_trait]
+    impl Geyser for StandIn {
+        type SubscribeStream = UpdateStream;
+
+        async fn subscribe(
+            &self,
+            request: Request<Streaming<SubscribeRequest>>,
+        ) -> Result<Response<Self::SubscribeStream>, Status> {
+            if request.metadata().get("x-token").map(|v| v.as_bytes()) != Some(X_TOKEN.as_bytes()) {
+                return Err(Status::unauthenticated("missing x-token"));
             }
+            let mut requests = request.into_inner();
+            let first = requests.message().await?.unwrap();
+            let filter = &first.transactions["pump_fun"];
+            assert_eq!(
+                filter.account_include,
+                vec![PUMP_FUN_PROGRAM_ID.to_string()]
+            );
+
+            let update = self.update.clone();
+            let stream =
+                futures::stream::once(async move { Ok(update) }).chain(futures::stream::pending());
+            Ok(Response::new(Box::pin(stream)))
+        }
+
+        async fn ping(&self, _: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+            Err(Status::unimplemented("ping"))
+        }
+
+        async fn get_latest_blockhash(
+            &self,
+            _: Request<GetLatestBlockhashRequest>,
+        ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+            Err(Status::unimplemented("get_latest_blockhash"))
+        }
+
+        async fn get_block_height(
+            &self,
+            _: Request<GetBlockHeightRequest>,
+        ) -> Result<Response<GetBlockHeightResponse>, Status> {
+            Err(Status::unimplemented("get_block_height"))
         }
+
+        async fn get_slot(
+            &self,
+            _: Request<GetSlotRequest>,
+        ) -> Result<Response<GetSlotResponse>, Status> {
+            Err(Status::unimplemented("get_slot"))
+        }
+
+        async fn is_blockhash_valid(
+            &self,
+            _: Request<IsBlockhashValidRequest>,
+        ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+            Err(Status::unimplemented("is_blockhash_valid"))
+        }
+
+        async fn get_version(
+            &self,
+            _: Request<GetVersionRequest>,
+        ) -> Result<Response<GetVersionResponse>, Status> {
+            Err(Status::unimplemented("get_version"))
+        }
+    }
+
+    /// Builds a transaction update with a legacy `create` for `mint` signed by
+    /// `user`. Account keys: [user, mint, bonding_curve, abc, program, filler...].
+    fn create_update(user: Pubkey, mint: Pubkey) -> SubscribeUpdate {
+        let mut keys = vec![
+            user,
+            mint,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            PUMP_FUN_PROGRAM_ID,
+        ];
+        keys.extend((0..8).map(|_| Pubkey::new_unique()));
+
+        let mut data = CREATE_DISCRIMINATOR.to_vec();
+        for value in ["Frog", "FROG", "ipfs://frog"] {
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            data.extend_from_slice(value.as_bytes());
+        }
+        data.extend_from_slice(user.as_ref());
+
+        // mint, mint_authority, bonding_curve, abc, global, mpl, metadata, user,
+        // system, token, ata, rent, event_authority, program
+        let accounts = vec![1, 5, 2, 3, 6, 7, 8, 0, 9, 10, 11, 12, 4, 4];
+        let instruction = CompiledInstruction {
+            program_id_index: 4,
+            accounts,
+            data,
+        };
+
+        SubscribeUpdate {
+            filters: vec!["pump_fun".to_string()],
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                slot: 42,
+                transaction: Some(SubscribeUpdateTransactionInfo {
+                    signature: vec![7; 64],
+                    transaction: Some(Transaction {
+                        signatures: vec![vec![7; 64]],
+                        message: Some(Message {
+                            account_keys: keys.iter().map(|k| k.to_bytes().to_vec()).collect(),
+                            instructions: vec![instruction],
+                            ..Default::default()
+                        }),
+                    }),
+                    meta: Some(TransactionStatusMeta::default()),
+                    ..Default::default()
+                }),
+            })),
+        }
+    }
+
+    fn test_config(endpoint: &str) -> Config {
+        toml::from_str(&format!(
+            r#"
+            keypair_path = "unused.json"
+            purchase_strategy = {{ fixed_sol = 0.1 }}
+
+            [endpoints]
+            rpc_http_url = "http://127.0.0.1:8899"
+            laserstream_grpc_url = "{endpoint}"
+            laserstream_x_token = "{X_TOKEN}"
+
+            [fee_config]
+
+            [dev_filters]
+            "#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn decodes_create_from_transaction_update() {
+        let user = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let events: Vec<TokenEvent> = parse_update(create_update(user, mint), &PUMP_FUN_PROGRAM_ID)
+            .into_iter()
+            .filter_map(SourceEvent::into_token)
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].mint, mint);
+        assert_eq!(events[0].developer, user);
+        assert_eq!(events[0].slot, Some(42));
+        assert_eq!(events[0].signature, Some(Signature::from([7; 64])));
+        assert_eq!(events[0].name, "Frog");
+    }
+
+    #[tokio::test]
+    async fn streams_events_from_stand_in_server() {
+        let user = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        let server = tokio::spawn(
+            Server::builder()
+                .add_service(GeyserServer::new(StandIn {
+                    update: create_update(user, mint),
+                }))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let endpoint = format!("http://{addr}");
+        let config = Arc::new(test_config(&endpoint));
+        let (tx, mut rx) = EventSender::for_test();
+        let source = LaserStreamSource::new(endpoint, config, None);
+        let listener = tokio::spawn(async move { source.run(tx).await });
+
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .and_then(SourceEvent::into_token)
+            .unwrap();
+        assert_eq!(event.mint, mint);
+        assert_eq!(event.developer, user);
+        assert!(matches!(event.source, EventSourceKind::LaserStream));
+
+        listener.abort();
+        server.abort();
     }
 }
@@ -0,0 +1,277 @@
+use std::time::{Instant, SystemTime};
+
+use solana_sdk::{pubkey, pubkey::Pubkey, signature::Signature};
+
+use super::{
+    pump_fun::{self, BorshReader, CompiledInstruction},
+    EventSourceKind, Platform, TokenEvent,
+};
+
+/// Raydium LaunchLab program, used by Let's Bonk launches.
+pub const LAUNCHLAB_PROGRAM_ID: Pubkey = pubkey!("LanMV9sAd7wArD4vJFi2qDdfnVhFxYSUg6eADduJ3uj");
+pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+/// Platform config that marks a LaunchLab pool as a Let's Bonk launch.
+pub const LETS_BONK_PLATFORM_CONFIG: Pubkey =
+    pubkey!("5thqcDwKp5QQ8US4XRMoseGeGbmLKMmoKZmS6zHrQAsA");
+
+/// Anchor discriminators from `idl/raydium_launchlab_idl.json`.
+pub const POOL_CREATE_EVENT_DISCRIMINATOR: [u8; 8] = [151, 215, 226, 9, 118, 161, 115, 174];
+pub const INITIALIZE_DISCRIMINATOR: [u8; 8] = [175, 175, 109, 31, 13, 152, 155, 237];
+pub const INITIALIZE_V2_DISCRIMINATOR: [u8; 8] = [67, 153, 175, 39, 218, 16, 38, 32];
+pub const INITIALIZE_WITH_TOKEN_2022_DISCRIMINATOR: [u8; 8] = [37, 190, 126, 222, 44, 154, 171, 17];
+
+/// `MintParams` shared by the initialize instructions and `PoolCreateEvent`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MintParams {
+    pub decimals: u8,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl MintParams {
+    fn read(reader: &mut BorshReader) -> Option<Self> {
+        Some(MintParams {
+            decimals: reader.take(1)?[0],
+            name: reader.string()?,
+            symbol: reader.string()?,
+            uri: reader.string()?,
+        })
+    }
+}
+
+/// Decoded LaunchLab `PoolCreateEvent`, up to the mint parameters. The event
+/// names the pool but not the base mint, so creates come from instructions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolCreateEvent {
+    pub pool_state: Pubkey,
+    pub creator: Pubkey,
+    pub config: Pubkey,
+    pub base_mint_param: MintParams,
+}
+
+impl PoolCreateEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let body = data.strip_prefix(&POOL_CREATE_EVENT_DISCRIMINATOR)?;
+        let mut reader = BorshReader::new(body);
+
+        Some(PoolCreateEvent {
+            pool_state: reader.pubkey()?,
+            creator: reader.pubkey()?,
+            config: reader.pubkey()?,
+            base_mint_param: MintParams::read(&mut reader)?,
+        })
+    }
+}
+
+/// Decoded LaunchLab `initialize`, `initialize_v2` or
+/// `initialize_with_token_2022` instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InitializeInstruction {
+    pub base_mint_param: MintParams,
+    pub creator: Pubkey,
+    pub platform_config: Pubkey,
+    pub pool_state: Pubkey,
+    pub base_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl InitializeInstruction {
+    /// Decodes instruction `data` given the instruction's resolved account keys,
+    /// in the order listed by the IDL.
+    pub fn decode(data: &[u8], accounts: &[Pubkey]) -> Option<Self> {
+        let (body, token_program_index) =
+            if let Some(body) = data.strip_prefix(&INITIALIZE_WITH_TOKEN_2022_DISCRIMINATOR) {
+                // No metadata account ahead of the token programs.
+                (body, 10)
+            } else {
+                let body = data
+                    .strip_prefix(&INITIALIZE_DISCRIMINATOR)
+                    .or_else(|| data.strip_prefix(&INITIALIZE_V2_DISCRIMINATOR))?;
+                (body, 11)
+            };
+        let mut reader = BorshReader::new(body);
+
+        Some(InitializeInstruction {
+            base_mint_param: MintParams::read(&mut reader)?,
+            creator: *accounts.get(1)?,
+            platform_config: *accounts.get(3)?,
+            pool_state: *accounts.get(5)?,
+            base_mint: *accounts.get(6)?,
+            base_vault: *accounts.get(8)?,
+            token_program: *accounts.get(token_program_index)?,
+        })
+    }
+
+    pub fn into_token_event(
+        self,
+        source: EventSourceKind,
+        slot: Option<u64>,
+        signature: Option<Signature>,
+    ) -> TokenEvent {
+        TokenEvent {
+            mint: self.base_mint,
+            developer: self.creator,
+            platform: Platform::LetsBonk,
+            name: self.base_mint_param.name,
+            symbol: self.base_mint_param.symbol,
+            uri: self.base_mint_param.uri,
+            bonding_curve: self.pool_state,
+            associated_bonding_curve: self.base_vault,
+            creator: self.creator,
+            creator_vault: None,
+            token_program: self.token_program,
+            token_program_inferred: false,
+            is_mayhem_mode: false,
+            source,
+            slot,
+            signature,
+            received_at: SystemTime::now(),
+            received_instant: Instant::now(),
+            backfill_age: None,
+            initial_buy: None,
+        }
+    }
+}
+
+/// Decodes Let's Bonk launches from a transaction's compiled instructions,
+/// in the same form `pump_fun::create_instructions` takes. Metadata comes
+/// from the logged `PoolCreateEvent` when present, since that is what the
+/// program stored.
+pub fn token_events<'a, I, S>(
+    keys: &[Pubkey],
+    instructions: I,
+    logs: &[S],
+    source: EventSourceKind,
+    slot: Option<u64>,
+    signature: Option<Signature>,
+) -> Vec<TokenEvent>
+where
+    I: IntoIterator<Item = CompiledInstruction<'a>>,
+    S: AsRef<str>,
+{
+    let mut initializes: Vec<InitializeInstruction> = instructions
+        .into_iter()
+        .filter(|(program_index, _, _)| keys.get(*program_index) == Some(&LAUNCHLAB_PROGRAM_ID))
+        .filter_map(|(_, accounts, data)| {
+            let accounts: Option<Vec<Pubkey>> = accounts
+                .iter()
+                .map(|index| keys.get(*index as usize).copied())
+                .collect();
+            InitializeInstruction::decode(data, &accounts?)
+        })
+        .filter(|ix| ix.platform_config == LETS_BONK_PLATFORM_CONFIG)
+        .collect();
+    if initializes.is_empty() {
+        return vec![];
+    }
+
+    for event in pump_fun::program_data(logs, &LAUNCHLAB_PROGRAM_ID)
+        .filter_map(|data| PoolCreateEvent::decode(&data))
+    {
+        if let Some(ix) = initializes
+            .iter_mut()
+            .find(|ix| ix.pool_state == event.pool_state)
+        {
+            ix.base_mint_param = event.base_mint_param;
+        }
+    }
+
+    initializes
+        .into_iter()
+        .map(|ix| ix.into_token_event(source, slot, signature))
+        .collect()
+}
+
+/// Derives the LaunchLab pool state PDA for a token quoted in WSOL.
+pub fn pool_state(base_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"pool", base_mint.as_ref(), WSOL_MINT.as_ref()],
+        &LAUNCHLAB_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Derives the pool's base-token vault, the LaunchLab counterpart of the
+/// pump.fun associated bonding curve.
+pub fn base_vault(pool_state: &Pubkey, base_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"pool_vault", pool_state.as_ref(), base_mint.as_ref()],
+        &LAUNCHLAB_PROGRAM_ID,
+    )
+    .0
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    use super::*;
+
+    fn mint_params(data: &mut Vec<u8>, name: &str) {
+        data.push(6);
+        for value in [name, "BONK", "https://bonk"] {
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            data.extend_from_slice(value.as_bytes());
+        }
+    }
+
+    #[test]
+    fn decodes_lets_bonk_launch_with_logged_metadata() {
+        let mut accounts: Vec<Pubkey> = (0..15).map(|_| Pubkey::new_unique()).collect();
+        accounts[3] = LETS_BONK_PLATFORM_CONFIG;
+        accounts[10] = pump_fun::TOKEN_2022_PROGRAM_ID;
+        let mut keys = accounts.clone();
+        keys.push(LAUNCHLAB_PROGRAM_ID);
+        let indexes: Vec<u8> = (0..15).collect();
+
+        let mut data = INITIALIZE_WITH_TOKEN_2022_DISCRIMINATOR.to_vec();
+        mint_params(&mut data, "Bonk");
+        // Curve, vesting and fee args are not decoded.
+        data.extend_from_slice(&[0; 40]);
+
+        let mut event = POOL_CREATE_EVENT_DISCRIMINATOR.to_vec();
+        for key in [accounts[5], accounts[1], Pubkey::new_unique()] {
+            event.extend_from_slice(key.as_ref());
+        }
+        mint_params(&mut event, "Bonk Inu");
+        let program = LAUNCHLAB_PROGRAM_ID.to_string();
+        let logs = [
+            format!("Program {program} invoke [1]"),
+            format!("Program data: {}", STANDARD.encode(&event)),
+            format!("Program {program} success"),
+        ];
+
+        let events = token_events(
+            &keys,
+            [(15, indexes.as_slice(), data.as_slice())],
+            &logs,
+            EventSourceKind::Block,
+            Some(9),
+            None,
+        );
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.platform, Platform::LetsBonk);
+        assert_eq!(event.mint, accounts[6]);
+        assert_eq!(event.developer, accounts[1]);
+        assert_eq!(event.bonding_curve, accounts[5]);
+        assert_eq!(event.associated_bonding_curve, accounts[8]);
+        assert_eq!(event.token_program, pump_fun::TOKEN_2022_PROGRAM_ID);
+        assert_eq!(event.name, "Bonk Inu");
+        assert_eq!(event.symbol, "BONK");
+
+        // Other LaunchLab platforms are not Let's Bonk launches.
+        keys[3] = Pubkey::new_unique();
+        let events = token_events(
+            &keys,
+            [(15, indexes.as_slice(), data.as_slice())],
+            &logs,
+            EventSourceKind::Block,
+            None,
+            None,
+        );
+        assert!(events.is_empty());
+    }
+}
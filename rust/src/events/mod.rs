@@ -1,59 +1,439 @@
+pub mod backfill;
+pub mod block;
+pub mod capture;
+pub mod copytrade;
+pub mod dedup;
 pub mod laserstream;
+pub mod launchlab;
+pub mod pump_fun;
+pub mod pump_swap;
+pub mod pumpportal;
+pub mod queue;
+pub mod source;
 pub mod websocket;
+pub mod ws_pool;
 
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
-use solana_sdk::pubkey::Pubkey;
-use tokio::sync::mpsc::UnboundedReceiver;
+use anyhow::Result;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
 
-use crate::config::Config;
+use dedup::EventSender;
+use queue::{QueueReceiver, QueueStats};
+use source::{EventSource, Health, SourceRegistry};
 
-#[derive(Clone, Debug)]
+use crate::config::{Config, EventQueueConfig};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EventSourceKind {
     LaserStream,
     WebSocket,
+    Block,
+    PumpPortal,
+    CopyTrade,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    PumpFun,
+    LetsBonk,
 }
 
+/// A newly created token, filled in identically by every event source.
 #[derive(Clone, Debug)]
 pub struct TokenEvent {
     pub mint: Pubkey,
+    /// Wallet that signed the create transaction.
     pub developer: Pubkey,
+    pub platform: Platform,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    /// Pump.fun bonding curve, or the LaunchLab pool state for Let's Bonk.
+    pub bonding_curve: Pubkey,
+    /// Curve-owned token account (the LaunchLab base vault for Let's Bonk).
+    pub associated_bonding_curve: Pubkey,
+    /// Fee recipient recorded on the curve; usually the developer.
+    pub creator: Pubkey,
+    /// Pump.fun creator fee vault; `None` on other platforms.
+    pub creator_vault: Option<Pubkey>,
+    /// SPL Token for legacy `create`, Token-2022 for `create_v2`.
+    pub token_program: Pubkey,
+    /// Set when the source didn't report the token program, so
+    /// `token_program` and `associated_bonding_curve` are a guess to be
+    /// checked against the mint's owner before buying.
+    pub token_program_inferred: bool,
+    pub is_mayhem_mode: bool,
+    pub source: EventSourceKind,
+    pub slot: Option<u64>,
+    pub signature: Option<Signature>,
+    pub received_at: SystemTime,
+    /// Monotonic receive stamp that hot-path latency is measured from.
+    pub received_instant: Instant,
+    /// Set on creates recovered over RPC after a feed gap: how long before
+    /// recovery the create landed on chain.
+    pub backfill_age: Option<Duration>,
+    /// The developer's buy in the create transaction, where the source
+    /// reports it.
+    pub initial_buy: Option<InitialBuy>,
+}
+
+impl TokenEvent {
+    /// The pump.fun create with its token program confirmed as
+    /// `token_program`, e.g. from the mint account's owner.
+    pub fn with_token_program(self, token_program: Pubkey) -> Self {
+        Self {
+            associated_bonding_curve: pump_fun::associated_token_address(
+                &self.bonding_curve,
+                &self.mint,
+                &token_program,
+            ),
+            token_program,
+            token_program_inferred: false,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+impl TokenEvent {
+    /// A legacy pump.fun create from the WebSocket source, received now,
+    /// that tests adjust with struct update syntax.
+    pub fn for_test(mint: Pubkey, developer: Pubkey) -> Self {
+        Self {
+            mint,
+            developer,
+            platform: Platform::PumpFun,
+            name: String::new(),
+            symbol: String::new(),
+            uri: String::new(),
+            bonding_curve: Pubkey::new_unique(),
+            associated_bonding_curve: Pubkey::new_unique(),
+            creator: developer,
+            creator_vault: None,
+            token_program: pump_fun::TOKEN_PROGRAM_ID,
+            token_program_inferred: false,
+            is_mayhem_mode: false,
+            source: EventSourceKind::WebSocket,
+            slot: None,
+            signature: None,
+            received_at: SystemTime::now(),
+            received_instant: Instant::now(),
+            backfill_age: None,
+            initial_buy: None,
+        }
+    }
+}
+
+/// SOL the developer spent and tokens they received buying into their own
+/// launch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InitialBuy {
+    pub lamports: u64,
+    pub tokens: u64,
+}
+
+impl InitialBuy {
+    pub fn sol(&self) -> f64 {
+        self.lamports as f64 / 1e9
+    }
+
+    /// Share of the total supply bought, in percent.
+    pub fn supply_pct(&self) -> f64 {
+        self.tokens as f64 * 100.0 / pump_fun::TOKEN_TOTAL_SUPPLY as f64
+    }
+}
+
+/// Restart delay after a source crashes; doubles up to the cap and resets once
+/// a run lasts longer than the cap.
+const RESTART_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// A pump.fun trade seen by a source, with its provenance.
+#[derive(Clone, Debug)]
+pub struct TradeUpdate {
+    pub trade: pump_fun::TradeEvent,
     pub source: EventSourceKind,
+    pub slot: Option<u64>,
+    pub signature: Option<Signature>,
+    /// Position among the transaction's trade events; with the signature it
+    /// identifies the trade across sources.
+    pub ordinal: u32,
+    pub received_at: SystemTime,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationKind {
+    /// pump.fun `CompleteEvent`: the curve filled and trading on it stopped.
+    CurveComplete,
+    /// pump.fun `CompletePumpAmmMigrationEvent`: liquidity moved to PumpSwap.
+    Migrated,
+    /// PumpSwap `CreatePoolEvent` for a migrated curve.
+    PoolCreated,
+}
+
+/// A pump.fun token graduating to PumpSwap. `pool` is the canonical
+/// migration pool, derived for `CurveComplete` before it exists on chain.
+#[derive(Clone, Debug)]
+pub struct MigrationEvent {
+    pub kind: MigrationKind,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub source: EventSourceKind,
+    pub slot: Option<u64>,
+    pub signature: Option<Signature>,
+    /// Position among the transaction's migration events.
+    pub ordinal: u32,
+    pub received_at: SystemTime,
+}
+
+/// A tracked wallet's trade, as its venue logged it.
+#[derive(Clone, Debug)]
+pub enum LeaderTrade {
+    Curve(pump_fun::TradeEvent),
+    Swap(pump_swap::SwapEvent),
+}
+
+/// A buy or sell by a copy-trading leader.
+#[derive(Clone, Debug)]
+pub struct CopyTradeEvent {
+    pub leader: Pubkey,
+    pub trade: LeaderTrade,
+    pub source: EventSourceKind,
+    pub slot: Option<u64>,
+    pub signature: Option<Signature>,
+    /// Position among the transaction's leader trades.
+    pub ordinal: u32,
+    pub received_at: SystemTime,
+}
+
+impl CopyTradeEvent {
+    pub fn is_buy(&self) -> bool {
+        match &self.trade {
+            LeaderTrade::Curve(trade) => trade.is_buy,
+            LeaderTrade::Swap(swap) => swap.is_buy,
+        }
+    }
+
+    /// SOL the leader spent or received, in lamports.
+    pub fn sol_amount(&self) -> u64 {
+        match &self.trade {
+            LeaderTrade::Curve(trade) => trade.sol_amount,
+            LeaderTrade::Swap(swap) => swap.quote_amount,
+        }
+    }
+
+    pub fn token_amount(&self) -> u64 {
+        match &self.trade {
+            LeaderTrade::Curve(trade) => trade.token_amount,
+            LeaderTrade::Swap(swap) => swap.base_amount,
+        }
+    }
+}
+
+/// Everything a source can emit into the pipeline.
+#[derive(Clone, Debug)]
+pub enum SourceEvent {
+    Token(TokenEvent),
+    Trade(TradeUpdate),
+    Migration(MigrationEvent),
+    CopyTrade(CopyTradeEvent),
+}
+
+impl SourceEvent {
+    pub fn slot(&self) -> Option<u64> {
+        match self {
+            SourceEvent::Token(event) => event.slot,
+            SourceEvent::Trade(update) => update.slot,
+            SourceEvent::Migration(event) => event.slot,
+            SourceEvent::CopyTrade(event) => event.slot,
+        }
+    }
+}
+
+#[cfg(test)]
+impl SourceEvent {
+    pub fn into_token(self) -> Option<TokenEvent> {
+        match self {
+            SourceEvent::Token(event) => Some(event),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct EventSupervisor {
-    config: Arc<Config>,
+    registry: SourceRegistry,
+    race_stats: dedup::RaceStats,
+    restarts: Arc<DashMap<String, u32>>,
+    queue: EventQueueConfig,
+    queue_stats: QueueStats,
 }
 
 impl EventSupervisor {
-    pub fn new(config: Arc<Config>) -> Self {
-        Self { config }
+    pub fn new(config: Arc<Config>) -> Result<Self> {
+        let registry = SourceRegistry::from_config(&config)?;
+        Ok(Self::with_registry(registry, config.event_queue.clone()))
     }
 
-    pub fn start(&self) -> UnboundedReceiver<TokenEvent> {
-        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let laserstream = self.config.endpoints.laserstream_grpc_url.clone();
-        let ws = self.config.endpoints.ws_url.clone();
-        let config = self.config.clone();
-
-        if let Some(endpoint) = laserstream {
-            let tx_clone = tx.clone();
-            tokio::spawn(async move {
-                if let Err(err) = laserstream::run(endpoint, tx_clone).await {
-                    log::warn!("LaserStream listener exited: {err}");
-                }
-            });
+    pub fn with_registry(registry: SourceRegistry, queue: EventQueueConfig) -> Self {
+        Self {
+            registry,
+            race_stats: dedup::RaceStats::default(),
+            restarts: Arc::new(DashMap::new()),
+            queue,
+            queue_stats: QueueStats::default(),
         }
+    }
+
+    /// Which source delivered each create first, across all configured feeds.
+    pub fn race_stats(&self) -> &dedup::RaceStats {
+        &self.race_stats
+    }
+
+    /// Depth of the pipeline queue and how many events it shed.
+    pub fn queue_stats(&self) -> &QueueStats {
+        &self.queue_stats
+    }
+
+    pub fn health(&self) -> Vec<(String, Health)> {
+        self.registry
+            .sources()
+            .iter()
+            .map(|source| {
+                let mut health = source.health();
+                health.restarts = self
+                    .restarts
+                    .get(source.name())
+                    .map_or(0, |restarts| *restarts);
+                (source.name().to_string(), health)
+            })
+            .collect()
+    }
+
+    /// Scores of every pooled endpoint, by source.
+    pub fn endpoint_scores(&self) -> Vec<(String, ws_pool::EndpointScore)> {
+        self.registry
+            .sources()
+            .iter()
+            .flat_map(|source| {
+                let name = source.name().to_string();
+                source
+                    .endpoints()
+                    .into_iter()
+                    .map(move |score| (name.clone(), score))
+            })
+            .collect()
+    }
 
-        if let Some(ws_endpoint) = ws {
-            tokio::spawn(async move {
-                if let Err(err) = websocket::run(ws_endpoint, config, tx).await {
-                    log::warn!("WebSocket listener exited: {err}");
-                }
-            });
+    /// Starts every registered source. Their events are deduplicated so the
+    /// returned receiver sees each create once, from whichever source won.
+    /// The receiver is bounded and sheds events it can't keep up with.
+    pub fn start(&self) -> QueueReceiver {
+        let (queue_tx, rx) = queue::channel(&self.queue, self.queue_stats.clone());
+        let tx = EventSender::new(queue_tx, self.race_stats.clone());
+
+        for source in self.registry.sources() {
+            tokio::spawn(supervise(source.clone(), tx.clone(), self.restarts.clone()));
         }
 
         rx
     }
 }
+
+/// Runs `source` until the receiver is dropped, restarting it with backoff
+/// whenever it returns an error or panics.
+async fn supervise(
+    source: Arc<dyn EventSource>,
+    tx: EventSender,
+    restarts: Arc<DashMap<String, u32>>,
+) {
+    let mut backoff = RESTART_BACKOFF_MIN;
+
+    loop {
+        let started = Instant::now();
+        let run = {
+            let source = source.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move { source.run(tx).await })
+        };
+
+        match run.await {
+            Ok(Ok(())) => {
+                log::info!("{} source stopped", source.name());
+                return;
+            }
+            Ok(Err(err)) => log::warn!("{} source failed: {err}", source.name()),
+            Err(err) => log::error!("{} source crashed: {err}", source.name()),
+        }
+        if tx.is_closed() {
+            return;
+        }
+
+        if started.elapsed() > RESTART_BACKOFF_MAX {
+            backoff = RESTART_BACKOFF_MIN;
+        }
+        log::info!("Restarting {} source in {backoff:?}", source.name());
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(RESTART_BACKOFF_MAX);
+        *restarts.entry(source.name().to_string()).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use async_trait::async_trait;
+
+    use super::*;
+
+    /// Fails its first run, then sends one event and waits for shutdown.
+    #[derive(Default)]
+    struct Flaky {
+        runs: AtomicU32,
+    }
+
+    #[async_trait]
+    impl EventSource for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn run(&self, tx: EventSender) -> Result<()> {
+            if self.runs.fetch_add(1, Ordering::SeqCst) == 0 {
+                anyhow::bail!("first run fails");
+            }
+            let _ = tx.send(SourceEvent::Token(TokenEvent::for_test(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            )));
+            while !tx.is_closed() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            Ok(())
+        }
+
+        fn health(&self) -> Health {
+            Health::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn restarts_failed_sources() {
+        let mut registry = SourceRegistry::default();
+        registry.register(Flaky::default());
+        let supervisor = EventSupervisor::with_registry(registry, EventQueueConfig::default());
+
+        let mut rx = supervisor.start();
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        assert!(event.unwrap().is_some());
+        assert_eq!(supervisor.health()[0].1.restarts, 1);
+    }
+}
@@ -0,0 +1,902 @@
+use std::time::{Instant, SystemTime};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{pubkey, pubkey::Pubkey, signature::Signature};
+
+use super::{
+    pump_swap, EventSourceKind, InitialBuy, MigrationEvent, MigrationKind, Platform, SourceEvent,
+    TokenEvent, TradeUpdate,
+};
+
+pub const PUMP_FUN_PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+pub const GLOBAL: Pubkey = pubkey!("4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf");
+pub const EVENT_AUTHORITY: Pubkey = pubkey!("Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1");
+pub const FEE_RECIPIENT: Pubkey = pubkey!("CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM");
+pub const MAYHEM_FEE_RECIPIENT: Pubkey = pubkey!("GesfTA3X2arioaHp8bbKdjG9vJtskViWACZoYvxp4twS");
+pub const FEE_PROGRAM_ID: Pubkey = pubkey!("pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ");
+
+/// Every pump.fun mint's supply in base units: 1B tokens with 6 decimals.
+pub const TOKEN_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
+
+/// Anchor event discriminator for `CreateEvent` (see `idl/pump_fun_idl.json`).
+pub const CREATE_EVENT_DISCRIMINATOR: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
+pub const TRADE_EVENT_DISCRIMINATOR: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+pub const COMPLETE_EVENT_DISCRIMINATOR: [u8; 8] = [95, 114, 97, 156, 212, 46, 152, 8];
+pub const COMPLETE_PUMP_AMM_MIGRATION_EVENT_DISCRIMINATOR: [u8; 8] =
+    [189, 233, 93, 185, 92, 148, 234, 148];
+/// Anchor instruction discriminators for `create` and `create_v2`.
+pub const CREATE_DISCRIMINATOR: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+pub const CREATE_V2_DISCRIMINATOR: [u8; 8] = [214, 144, 76, 236, 95, 139, 49, 180];
+pub const BUY_EXACT_SOL_IN_DISCRIMINATOR: [u8; 8] = [56, 252, 116, 8, 158, 223, 205, 95];
+pub const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Decoded pump.fun `CreateEvent`.
+///
+/// Older launches emit a shorter layout that stops after `user`; the trailing
+/// fields then fall back to the values the program used at the time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreateEvent {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub user: Pubkey,
+    pub creator: Pubkey,
+    pub timestamp: i64,
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub token_program: Pubkey,
+    pub is_mayhem_mode: bool,
+}
+
+impl CreateEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let body = data.strip_prefix(&CREATE_EVENT_DISCRIMINATOR)?;
+        let mut reader = BorshReader::new(body);
+
+        let name = reader.string()?;
+        let symbol = reader.string()?;
+        let uri = reader.string()?;
+        let mint = reader.pubkey()?;
+        let bonding_curve = reader.pubkey()?;
+        let user = reader.pubkey()?;
+
+        let mut event = CreateEvent {
+            name,
+            symbol,
+            uri,
+            mint,
+            bonding_curve,
+            user,
+            creator: user,
+            timestamp: 0,
+            virtual_token_reserves: 0,
+            virtual_sol_reserves: 0,
+            real_token_reserves: 0,
+            token_total_supply: 0,
+            token_program: TOKEN_PROGRAM_ID,
+            is_mayhem_mode: false,
+        };
+
+        if reader.is_empty() {
+            return Some(event);
+        }
+        event.creator = reader.pubkey()?;
+        event.timestamp = reader.i64()?;
+        event.virtual_token_reserves = reader.u64()?;
+        event.virtual_sol_reserves = reader.u64()?;
+        event.real_token_reserves = reader.u64()?;
+        event.token_total_supply = reader.u64()?;
+
+        if reader.is_empty() {
+            return Some(event);
+        }
+        event.token_program = reader.pubkey()?;
+        event.is_mayhem_mode = reader.bool()?;
+
+        Some(event)
+    }
+
+    pub fn into_token_event(
+        self,
+        source: EventSourceKind,
+        slot: Option<u64>,
+        signature: Option<Signature>,
+    ) -> TokenEvent {
+        let associated_bonding_curve =
+            associated_token_address(&self.bonding_curve, &self.mint, &self.token_program);
+        TokenEvent {
+            mint: self.mint,
+            developer: self.user,
+            platform: Platform::PumpFun,
+            name: self.name,
+            symbol: self.symbol,
+            uri: self.uri,
+            bonding_curve: self.bonding_curve,
+            associated_bonding_curve,
+            creator: self.creator,
+            creator_vault: Some(creator_vault(&self.creator)),
+            token_program: self.token_program,
+            token_program_inferred: false,
+            is_mayhem_mode: self.is_mayhem_mode,
+            source,
+            slot,
+            signature,
+            received_at: SystemTime::now(),
+            received_instant: Instant::now(),
+            backfill_age: None,
+            initial_buy: None,
+        }
+    }
+}
+
+/// Decoded pump.fun `TradeEvent`: the leading fields every version of the
+/// program has emitted, plus the fee accounts when the layout carries them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TradeEvent {
+    pub mint: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub fee_recipient: Option<Pubkey>,
+    pub creator: Option<Pubkey>,
+}
+
+impl TradeEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let body = data.strip_prefix(&TRADE_EVENT_DISCRIMINATOR)?;
+        let mut reader = BorshReader::new(body);
+
+        let mut event = TradeEvent {
+            mint: reader.pubkey()?,
+            sol_amount: reader.u64()?,
+            token_amount: reader.u64()?,
+            is_buy: reader.bool()?,
+            user: reader.pubkey()?,
+            timestamp: reader.i64()?,
+            virtual_sol_reserves: reader.u64()?,
+            virtual_token_reserves: reader.u64()?,
+            real_sol_reserves: reader.u64()?,
+            real_token_reserves: reader.u64()?,
+            fee_recipient: None,
+            creator: None,
+        };
+        event.fee_recipient = reader.pubkey();
+        // fee_basis_points, fee
+        if event.fee_recipient.is_some() && reader.take(16).is_some() {
+            event.creator = reader.pubkey();
+        }
+        Some(event)
+    }
+
+    /// Whether the trade paid the mayhem-mode fee recipient.
+    pub fn is_mayhem_mode(&self) -> bool {
+        self.fee_recipient == Some(MAYHEM_FEE_RECIPIENT)
+    }
+
+    pub fn into_update(
+        self,
+        source: EventSourceKind,
+        slot: Option<u64>,
+        signature: Option<Signature>,
+        ordinal: u32,
+    ) -> TradeUpdate {
+        TradeUpdate {
+            trade: self,
+            source,
+            slot,
+            signature,
+            ordinal,
+            received_at: SystemTime::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl TradeEvent {
+    /// A one-lamport trade of one token at timestamp zero, with empty
+    /// reserves, that tests adjust with struct update syntax.
+    pub fn for_test(mint: Pubkey, user: Pubkey, is_buy: bool) -> Self {
+        Self {
+            mint,
+            sol_amount: 1,
+            token_amount: 1,
+            is_buy,
+            user,
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+            real_sol_reserves: 0,
+            real_token_reserves: 0,
+            fee_recipient: None,
+            creator: None,
+        }
+    }
+}
+
+/// Decoded pump.fun `CompleteEvent`, logged when a buy fills the curve.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompleteEvent {
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub timestamp: i64,
+}
+
+impl CompleteEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let body = data.strip_prefix(&COMPLETE_EVENT_DISCRIMINATOR)?;
+        let mut reader = BorshReader::new(body);
+
+        Some(CompleteEvent {
+            user: reader.pubkey()?,
+            mint: reader.pubkey()?,
+            bonding_curve: reader.pubkey()?,
+            timestamp: reader.i64()?,
+        })
+    }
+}
+
+/// Decoded pump.fun `CompletePumpAmmMigrationEvent`, logged by `migrate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompletePumpAmmMigrationEvent {
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub mint_amount: u64,
+    pub sol_amount: u64,
+    pub pool_migration_fee: u64,
+    pub bonding_curve: Pubkey,
+    pub timestamp: i64,
+    pub pool: Pubkey,
+}
+
+impl CompletePumpAmmMigrationEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let body = data.strip_prefix(&COMPLETE_PUMP_AMM_MIGRATION_EVENT_DISCRIMINATOR)?;
+        let mut reader = BorshReader::new(body);
+
+        Some(CompletePumpAmmMigrationEvent {
+            user: reader.pubkey()?,
+            mint: reader.pubkey()?,
+            mint_amount: reader.u64()?,
+            sol_amount: reader.u64()?,
+            pool_migration_fee: reader.u64()?,
+            bonding_curve: reader.pubkey()?,
+            timestamp: reader.i64()?,
+            pool: reader.pubkey()?,
+        })
+    }
+}
+
+/// Decoded pump.fun `create` / `create_v2` instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreateInstruction {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub creator: Pubkey,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub user: Pubkey,
+    pub token_program: Pubkey,
+    pub is_mayhem_mode: bool,
+}
+
+impl CreateInstruction {
+    /// Decodes instruction `data` given the instruction's resolved account keys,
+    /// in the order listed by the IDL.
+    pub fn decode(data: &[u8], accounts: &[Pubkey]) -> Option<Self> {
+        let (is_v2, body) = if let Some(body) = data.strip_prefix(&CREATE_DISCRIMINATOR) {
+            (false, body)
+        } else {
+            (true, data.strip_prefix(&CREATE_V2_DISCRIMINATOR)?)
+        };
+        let mut reader = BorshReader::new(body);
+
+        let name = reader.string()?;
+        let symbol = reader.string()?;
+        let uri = reader.string()?;
+        // Legacy `create` calls predate the creator argument.
+        let creator = if reader.is_empty() {
+            None
+        } else {
+            Some(reader.pubkey()?)
+        };
+        let is_mayhem_mode = is_v2 && reader.bool().unwrap_or(false);
+
+        let (user_index, token_program_index) = if is_v2 { (5, 7) } else { (7, 9) };
+        let user = *accounts.get(user_index)?;
+
+        Some(CreateInstruction {
+            name,
+            symbol,
+            uri,
+            creator: creator.unwrap_or(user),
+            mint: *accounts.first()?,
+            bonding_curve: *accounts.get(2)?,
+            associated_bonding_curve: *accounts.get(3)?,
+            user,
+            token_program: *accounts.get(token_program_index)?,
+            is_mayhem_mode,
+        })
+    }
+
+    pub fn into_token_event(
+        self,
+        source: EventSourceKind,
+        slot: Option<u64>,
+        signature: Option<Signature>,
+    ) -> TokenEvent {
+        TokenEvent {
+            mint: self.mint,
+            developer: self.user,
+            platform: Platform::PumpFun,
+            name: self.name,
+            symbol: self.symbol,
+            uri: self.uri,
+            bonding_curve: self.bonding_curve,
+            associated_bonding_curve: self.associated_bonding_curve,
+            creator: self.creator,
+            creator_vault: Some(creator_vault(&self.creator)),
+            token_program: self.token_program,
+            token_program_inferred: false,
+            is_mayhem_mode: self.is_mayhem_mode,
+            source,
+            slot,
+            signature,
+            received_at: SystemTime::now(),
+            received_instant: Instant::now(),
+            backfill_age: None,
+            initial_buy: None,
+        }
+    }
+}
+
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Mint authority of every token `program` launches; only `create` and
+/// `create_v2` reference it.
+pub fn mint_authority(program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint-authority"], program).0
+}
+
+pub fn bonding_curve(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &PUMP_FUN_PROGRAM_ID).0
+}
+
+pub fn creator_vault(creator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"creator-vault", creator.as_ref()], &PUMP_FUN_PROGRAM_ID).0
+}
+
+pub fn global_volume_accumulator() -> Pubkey {
+    Pubkey::find_program_address(&[b"global_volume_accumulator"], &PUMP_FUN_PROGRAM_ID).0
+}
+
+pub fn user_volume_accumulator(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"user_volume_accumulator", user.as_ref()],
+        &PUMP_FUN_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn fee_config() -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"fee_config", PUMP_FUN_PROGRAM_ID.as_ref()],
+        &FEE_PROGRAM_ID,
+    )
+    .0
+}
+
+/// A compiled instruction as `(program_id_index, account_indexes, data)`,
+/// indexing into the full (static + loaded) account key list.
+pub type CompiledInstruction<'a> = (usize, &'a [u8], &'a [u8]);
+
+/// Decodes every pump.fun create instruction from a transaction's compiled
+/// instructions.
+pub fn create_instructions<'a, I>(
+    keys: &[Pubkey],
+    instructions: I,
+    program: &Pubkey,
+) -> Vec<CreateInstruction>
+where
+    I: IntoIterator<Item = CompiledInstruction<'a>>,
+{
+    instructions
+        .into_iter()
+        .filter(|(program_index, _, _)| keys.get(*program_index) == Some(program))
+        .filter_map(|(_, accounts, data)| {
+            let accounts: Option<Vec<Pubkey>> = accounts
+                .iter()
+                .map(|index| keys.get(*index as usize).copied())
+                .collect();
+            CreateInstruction::decode(data, &accounts?)
+        })
+        .collect()
+}
+
+/// Extracts the base64 payloads emitted by `program` from a transaction's log
+/// messages, tracking the invoke stack so CPI output from other programs is
+/// ignored.
+pub fn program_data<'a, S: AsRef<str>>(
+    logs: &'a [S],
+    program: &Pubkey,
+) -> impl Iterator<Item = Vec<u8>> + 'a {
+    let program = program.to_string();
+    let mut stack: Vec<&'a str> = Vec::new();
+
+    logs.iter().filter_map(move |line| {
+        let line = line.as_ref();
+        if let Some(payload) = line.strip_prefix(PROGRAM_DATA_PREFIX) {
+            if stack.last().copied() != Some(program.as_str()) {
+                return None;
+            }
+            let encoded = payload.split_whitespace().next()?;
+            return STANDARD.decode(encoded).ok();
+        }
+
+        let mut parts = line.split_whitespace();
+        if parts.next() != Some("Program") {
+            return None;
+        }
+        let (Some(id), Some(action)) = (parts.next(), parts.next()) else {
+            return None;
+        };
+        match action {
+            "invoke" => stack.push(id),
+            "success" | "failed:" => {
+                stack.pop();
+            }
+            _ => {}
+        }
+        None
+    })
+}
+
+/// Returns every `CreateEvent` the pump.fun program logged in a transaction.
+pub fn create_events_from_logs<S: AsRef<str>>(logs: &[S], program: &Pubkey) -> Vec<CreateEvent> {
+    program_data(logs, program)
+        .filter_map(|data| CreateEvent::decode(&data))
+        .collect()
+}
+
+/// Returns every `TradeEvent` the pump.fun program logged in a transaction.
+pub fn trade_events_from_logs<S: AsRef<str>>(logs: &[S], program: &Pubkey) -> Vec<TradeEvent> {
+    program_data(logs, program)
+        .filter_map(|data| TradeEvent::decode(&data))
+        .collect()
+}
+
+/// Decodes a transaction's logs into the source events it carries: creates
+/// first, then trades and migrations in log order.
+pub fn source_events_from_logs<S: AsRef<str>>(
+    logs: &[S],
+    program: &Pubkey,
+    source: EventSourceKind,
+    slot: Option<u64>,
+    signature: Option<Signature>,
+) -> Vec<SourceEvent> {
+    let mut events: Vec<SourceEvent> = create_events_from_logs(logs, program)
+        .into_iter()
+        .map(|event| SourceEvent::Token(event.into_token_event(source, slot, signature)))
+        .collect();
+    events.extend(logged_updates(logs, program, source, slot, signature));
+    attach_initial_buys(&mut events);
+    events
+}
+
+/// Fills in each create's `initial_buy` from its developer's buys of the same
+/// mint among the events decoded from one transaction. Creates the developer
+/// didn't buy into keep what their source reported.
+pub fn attach_initial_buys(events: &mut [SourceEvent]) {
+    let buys: Vec<TradeEvent> = events
+        .iter()
+        .filter_map(|event| match event {
+            SourceEvent::Trade(update) if update.trade.is_buy => Some(update.trade.clone()),
+            _ => None,
+        })
+        .collect();
+    for event in events.iter_mut() {
+        let SourceEvent::Token(token) = event else {
+            continue;
+        };
+        let own = buys
+            .iter()
+            .filter(|buy| buy.mint == token.mint && buy.user == token.developer);
+        for buy in own {
+            let initial = token.initial_buy.get_or_insert(InitialBuy {
+                lamports: 0,
+                tokens: 0,
+            });
+            initial.lamports = initial.lamports.saturating_add(buy.sol_amount);
+            initial.tokens = initial.tokens.saturating_add(buy.token_amount);
+        }
+    }
+}
+
+/// Decodes the trades and migrations in a transaction's logs. Sources that
+/// read creates from instructions use this for everything else.
+pub fn logged_updates<S: AsRef<str>>(
+    logs: &[S],
+    program: &Pubkey,
+    source: EventSourceKind,
+    slot: Option<u64>,
+    signature: Option<Signature>,
+) -> Vec<SourceEvent> {
+    let trades = trade_updates_from_logs(logs, program, source, slot, signature)
+        .into_iter()
+        .map(SourceEvent::Trade);
+    let migrations = migration_events_from_logs(logs, program, source, slot, signature)
+        .into_iter()
+        .map(SourceEvent::Migration);
+    trades.chain(migrations).collect()
+}
+
+/// Decodes curve completions, AMM migrations and the PumpSwap pools created
+/// for them. User-created PumpSwap pools are ignored.
+pub fn migration_events_from_logs<S: AsRef<str>>(
+    logs: &[S],
+    program: &Pubkey,
+    source: EventSourceKind,
+    slot: Option<u64>,
+    signature: Option<Signature>,
+) -> Vec<MigrationEvent> {
+    let pump_fun = program_data(logs, program).filter_map(|data| {
+        if let Some(event) = CompleteEvent::decode(&data) {
+            let pool = pump_swap::canonical_pool(&event.mint);
+            return Some((MigrationKind::CurveComplete, event.mint, pool));
+        }
+        let event = CompletePumpAmmMigrationEvent::decode(&data)?;
+        Some((MigrationKind::Migrated, event.mint, event.pool))
+    });
+    let pump_swap = program_data(logs, &pump_swap::PUMP_SWAP_PROGRAM_ID).filter_map(|data| {
+        let event = pump_swap::CreatePoolEvent::decode(&data)?;
+        event
+            .is_migration()
+            .then_some((MigrationKind::PoolCreated, event.base_mint, event.pool))
+    });
+
+    pump_fun
+        .chain(pump_swap)
+        .zip(0..)
+        .map(|((kind, mint, pool), ordinal)| MigrationEvent {
+            kind,
+            mint,
+            pool,
+            source,
+            slot,
+            signature,
+            ordinal,
+            received_at: SystemTime::now(),
+        })
+        .collect()
+}
+
+/// Decodes a transaction's `TradeEvent`s, numbering them in log order.
+pub fn trade_updates_from_logs<S: AsRef<str>>(
+    logs: &[S],
+    program: &Pubkey,
+    source: EventSourceKind,
+    slot: Option<u64>,
+    signature: Option<Signature>,
+) -> Vec<TradeUpdate> {
+    trade_events_from_logs(logs, program)
+        .into_iter()
+        .zip(0..)
+        .map(|(trade, ordinal)| trade.into_update(source, slot, signature, ordinal))
+        .collect()
+}
+
+/// Minimal cursor over Borsh-encoded bytes.
+pub struct BorshReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BorshReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    pub fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    pub fn bool(&mut self) -> Option<bool> {
+        match self.take(1)?[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn pubkey(&mut self) -> Option<Pubkey> {
+        Some(Pubkey::new_from_array(self.take(32)?.try_into().ok()?))
+    }
+
+    pub fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    const CREATE_TX: &str =
+        include_str!("../../../learning-examples/raw_create_tx_from_getTransaction.json");
+    const BUY_TX: &str =
+        include_str!("../../../learning-examples/raw_buy_tx_from_getTransaction.json");
+
+    fn fixture_logs(raw: &str) -> Vec<String> {
+        let json: Value = serde_json::from_str(raw).unwrap();
+        serde_json::from_value(json["result"]["meta"]["logMessages"].clone()).unwrap()
+    }
+
+    #[test]
+    fn decodes_legacy_create_event_from_fixture() {
+        let events = create_events_from_logs(&fixture_logs(CREATE_TX), &PUMP_FUN_PROGRAM_ID);
+        assert_eq!(events.len(), 1);
+
+        let event = &events[0];
+        assert_eq!(event.name, "excited");
+        assert_eq!(event.symbol, "excited");
+        assert!(event.uri.starts_with("https://cf-ipfs.com/ipfs/"));
+        assert_eq!(
+            event.mint,
+            Pubkey::from_str("ER2N5eaDoC68kNhj7LyaScimzq7deaqxiw88rewvxaKp").unwrap()
+        );
+        assert_eq!(
+            event.user,
+            Pubkey::from_str("Fswrw3tgQL597kCexxLEhft6a7Su4CDoqwRMqqj4BEp5").unwrap()
+        );
+        assert_eq!(event.creator, event.user);
+        assert_eq!(event.token_program, TOKEN_PROGRAM_ID);
+
+        let token = events[0]
+            .clone()
+            .into_token_event(EventSourceKind::WebSocket, None, None);
+        assert_eq!(
+            token.associated_bonding_curve,
+            Pubkey::from_str("6eTEMemDi58KJE1rPEagqMsgn34xUWTkzdZTKM8EVbYF").unwrap()
+        );
+        assert_eq!(token.name, "excited");
+    }
+
+    #[test]
+    fn attaches_the_developers_buy_to_the_create() {
+        let events = source_events_from_logs(
+            &fixture_logs(CREATE_TX),
+            &PUMP_FUN_PROGRAM_ID,
+            EventSourceKind::WebSocket,
+            None,
+            None,
+        );
+        let token = events
+            .iter()
+            .find_map(|event| match event {
+                SourceEvent::Token(token) => Some(token),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            token.initial_buy,
+            Some(InitialBuy {
+                lamports: 1_100_000_000,
+                tokens: 37_951_768_488_745,
+            })
+        );
+        assert!((token.initial_buy.unwrap().supply_pct() - 3.795).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ignores_trade_only_logs() {
+        let events = create_events_from_logs(&fixture_logs(BUY_TX), &PUMP_FUN_PROGRAM_ID);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn decodes_trade_events_from_fixture() {
+        let trades = trade_events_from_logs(&fixture_logs(BUY_TX), &PUMP_FUN_PROGRAM_ID);
+        assert_eq!(trades.len(), 2);
+
+        let buy = &trades[0];
+        assert!(buy.is_buy);
+        assert_eq!(buy.sol_amount, 24_080_282);
+        assert_eq!(buy.token_amount, 605_426_095_720);
+        assert_eq!(buy.virtual_sol_reserves, 35_793_720_152);
+        assert_eq!(buy.real_token_reserves, 619_419_782_120_301);
+        assert_eq!(buy.creator, None);
+        assert!(!trades[1].is_buy);
+        assert_eq!(trades[1].mint, buy.mint);
+
+        let events = source_events_from_logs(
+            &fixture_logs(BUY_TX),
+            &PUMP_FUN_PROGRAM_ID,
+            EventSourceKind::WebSocket,
+            None,
+            None,
+        );
+        let ordinals: Vec<u32> = events
+            .iter()
+            .filter_map(|event| match event {
+                SourceEvent::Trade(update) => Some(update.ordinal),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ordinals, [0, 1]);
+    }
+
+    #[test]
+    fn ignores_data_from_other_programs() {
+        let other = Pubkey::new_unique();
+        let events = create_events_from_logs(&fixture_logs(CREATE_TX), &other);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn decodes_current_create_event_layout() {
+        let mint = Pubkey::new_unique();
+        let bonding_curve = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let token_program = Pubkey::new_unique();
+
+        let mut data = CREATE_EVENT_DISCRIMINATOR.to_vec();
+        for value in ["Cat", "CAT", "https://example.com/cat.json"] {
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            data.extend_from_slice(value.as_bytes());
+        }
+        for key in [mint, bonding_curve, user, creator] {
+            data.extend_from_slice(key.as_ref());
+        }
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        for value in [1u64, 2, 3, 4] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(token_program.as_ref());
+        data.push(1);
+
+        let event = CreateEvent::decode(&data).unwrap();
+        assert_eq!(event.symbol, "CAT");
+        assert_eq!(event.creator, creator);
+        assert_eq!(event.timestamp, 1_700_000_000);
+        assert_eq!(event.token_total_supply, 4);
+        assert_eq!(event.token_program, token_program);
+        assert!(event.is_mayhem_mode);
+
+        assert!(CreateEvent::decode(&data[..data.len() - 10]).is_none());
+    }
+
+    #[test]
+    fn decodes_create_v2_instruction() {
+        let accounts: Vec<Pubkey> = (0..16).map(|_| Pubkey::new_unique()).collect();
+        let creator = Pubkey::new_unique();
+
+        let mut data = CREATE_V2_DISCRIMINATOR.to_vec();
+        for value in ["Dog", "DOG", "ipfs://dog"] {
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            data.extend_from_slice(value.as_bytes());
+        }
+        data.extend_from_slice(creator.as_ref());
+        data.push(0);
+
+        let ix = CreateInstruction::decode(&data, &accounts).unwrap();
+        assert_eq!(ix.name, "Dog");
+        assert_eq!(ix.mint, accounts[0]);
+        assert_eq!(ix.associated_bonding_curve, accounts[3]);
+        assert_eq!(ix.user, accounts[5]);
+        assert_eq!(ix.token_program, accounts[7]);
+        assert_eq!(ix.creator, creator);
+        assert!(!ix.is_mayhem_mode);
+
+        assert!(CreateInstruction::decode(&data, &accounts[..4]).is_none());
+        assert!(CreateInstruction::decode(&data[8..], &accounts).is_none());
+    }
+
+    #[test]
+    fn decodes_migrations_from_logs() {
+        let mint = Pubkey::new_unique();
+        let bonding_curve = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+
+        let mut complete = COMPLETE_EVENT_DISCRIMINATOR.to_vec();
+        for key in [user, mint, bonding_curve] {
+            complete.extend_from_slice(key.as_ref());
+        }
+        complete.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+
+        let pump_fun = PUMP_FUN_PROGRAM_ID.to_string();
+        let pump_swap = pump_swap::PUMP_SWAP_PROGRAM_ID.to_string();
+        let logs = vec![
+            format!("Program {pump_fun} invoke [1]"),
+            format!("Program data: {}", STANDARD.encode(&complete)),
+            // A user pool for the same mint is not a migration.
+            format!("Program {pump_swap} invoke [2]"),
+            format!(
+                "Program data: {}",
+                STANDARD.encode(user_pool_event(&mint, &user))
+            ),
+            format!("Program {pump_swap} success"),
+            format!("Program {pump_fun} success"),
+        ];
+
+        let signature = Signature::new_unique();
+        let events = migration_events_from_logs(
+            &logs,
+            &PUMP_FUN_PROGRAM_ID,
+            EventSourceKind::WebSocket,
+            Some(7),
+            Some(signature),
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, MigrationKind::CurveComplete);
+        assert_eq!(events[0].mint, mint);
+        assert_eq!(events[0].pool, pump_swap::canonical_pool(&mint));
+        assert_eq!(events[0].signature, Some(signature));
+        assert_eq!(events[0].ordinal, 0);
+    }
+
+    fn user_pool_event(mint: &Pubkey, creator: &Pubkey) -> Vec<u8> {
+        let mut data = pump_swap::CREATE_POOL_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&0i64.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        for key in [creator, mint, &Pubkey::new_unique()] {
+            data.extend_from_slice(key.as_ref());
+        }
+        data.extend_from_slice(&[0; 2 + 2 * 8 + 5 * 8 + 1 + 4 * 32]);
+        data
+    }
+}
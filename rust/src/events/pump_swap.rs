@@ -0,0 +1,286 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+use super::{
+    launchlab::WSOL_MINT,
+    pump_fun::{self, BorshReader, FEE_PROGRAM_ID, PUMP_FUN_PROGRAM_ID},
+};
+
+/// PumpSwap AMM, where completed pump.fun curves migrate.
+pub const PUMP_SWAP_PROGRAM_ID: Pubkey = pubkey!("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA");
+pub const GLOBAL_CONFIG: Pubkey = pubkey!("ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw");
+pub const EVENT_AUTHORITY: Pubkey = pubkey!("GS4CU59F31iL7aR2Q8zVS8DRrcRnXX1yjQ66TqNVQnaR");
+
+/// Instruction discriminators (see `idl/pump_swap_idl.json`).
+pub const BUY_EXACT_QUOTE_IN_DISCRIMINATOR: [u8; 8] = [198, 46, 21, 82, 180, 217, 232, 112];
+pub const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+
+/// Offset of `base_mint` in a `Pool` account: discriminator, bump, index
+/// and creator come first.
+const POOL_BASE_MINT_OFFSET: usize = 8 + 1 + 2 + 32;
+
+/// Anchor event discriminator for `CreatePoolEvent` (see `idl/pump_swap_idl.json`).
+pub const CREATE_POOL_EVENT_DISCRIMINATOR: [u8; 8] = [177, 49, 12, 210, 160, 118, 167, 116];
+pub const BUY_EVENT_DISCRIMINATOR: [u8; 8] = [103, 244, 82, 31, 44, 245, 119, 119];
+pub const SELL_EVENT_DISCRIMINATOR: [u8; 8] = [62, 47, 55, 10, 165, 3, 220, 42];
+
+/// Decoded PumpSwap `CreatePoolEvent`, up to the accounts every version emits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreatePoolEvent {
+    pub timestamp: i64,
+    pub index: u16,
+    pub creator: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_amount_in: u64,
+    pub quote_amount_in: u64,
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+impl CreatePoolEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let body = data.strip_prefix(&CREATE_POOL_EVENT_DISCRIMINATOR)?;
+        let mut reader = BorshReader::new(body);
+
+        let timestamp = reader.i64()?;
+        let index = reader.u16()?;
+        let creator = reader.pubkey()?;
+        let base_mint = reader.pubkey()?;
+        let quote_mint = reader.pubkey()?;
+        // base/quote mint decimals
+        reader.take(2)?;
+        let base_amount_in = reader.u64()?;
+        let quote_amount_in = reader.u64()?;
+        // pool amounts, minimum/initial liquidity, lp_token_amount_out, bump
+        reader.take(5 * 8 + 1)?;
+        let pool = reader.pubkey()?;
+        let lp_mint = reader.pubkey()?;
+
+        Some(CreatePoolEvent {
+            timestamp,
+            index,
+            creator,
+            base_mint,
+            quote_mint,
+            base_amount_in,
+            quote_amount_in,
+            pool,
+            lp_mint,
+        })
+    }
+
+    /// Whether the pool was created by a pump.fun migration rather than a user.
+    pub fn is_migration(&self) -> bool {
+        self.index == 0 && self.creator == pool_authority(&self.base_mint)
+    }
+}
+
+/// A PumpSwap `BuyEvent` or `SellEvent`. The events name the pool but not
+/// its mints; `quote_amount` is what the user paid or received in total.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapEvent {
+    pub timestamp: i64,
+    pub is_buy: bool,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    /// Pool reserves before the trade.
+    pub pool_base_reserves: u64,
+    pub pool_quote_reserves: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub protocol_fee_recipient: Pubkey,
+    pub coin_creator: Pubkey,
+}
+
+impl SwapEvent {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (is_buy, body) = match data.strip_prefix(&BUY_EVENT_DISCRIMINATOR) {
+            Some(body) => (true, body),
+            None => (false, data.strip_prefix(&SELL_EVENT_DISCRIMINATOR)?),
+        };
+        let mut reader = BorshReader::new(body);
+
+        let timestamp = reader.i64()?;
+        let base_amount = reader.u64()?;
+        // quote limit, user reserves
+        reader.take(3 * 8)?;
+        let pool_base_reserves = reader.u64()?;
+        let pool_quote_reserves = reader.u64()?;
+        // quote amount before fees, lp and protocol fees, quote amount with
+        // lp fee
+        reader.take(6 * 8)?;
+        let quote_amount = reader.u64()?;
+        let pool = reader.pubkey()?;
+        let user = reader.pubkey()?;
+        // user token accounts
+        reader.take(2 * 32)?;
+        let protocol_fee_recipient = reader.pubkey()?;
+        reader.take(32)?;
+        let coin_creator = reader.pubkey()?;
+
+        Some(SwapEvent {
+            timestamp,
+            is_buy,
+            base_amount,
+            quote_amount,
+            pool_base_reserves,
+            pool_quote_reserves,
+            pool,
+            user,
+            protocol_fee_recipient,
+            coin_creator,
+        })
+    }
+
+    /// Pool reserves as the trade left them, fees aside.
+    pub fn reserves_after(&self) -> (u64, u64) {
+        if self.is_buy {
+            (
+                self.pool_base_reserves.saturating_sub(self.base_amount),
+                self.pool_quote_reserves.saturating_add(self.quote_amount),
+            )
+        } else {
+            (
+                self.pool_base_reserves.saturating_add(self.base_amount),
+                self.pool_quote_reserves.saturating_sub(self.quote_amount),
+            )
+        }
+    }
+}
+
+/// The base and quote mints of a `Pool` account's data.
+pub fn pool_mints(data: &[u8]) -> Option<(Pubkey, Pubkey)> {
+    let mut reader = BorshReader::new(data.get(POOL_BASE_MINT_OFFSET..)?);
+    Some((reader.pubkey()?, reader.pubkey()?))
+}
+
+pub fn coin_creator_vault_authority(coin_creator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"creator_vault", coin_creator.as_ref()],
+        &PUMP_SWAP_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn global_volume_accumulator() -> Pubkey {
+    Pubkey::find_program_address(&[b"global_volume_accumulator"], &PUMP_SWAP_PROGRAM_ID).0
+}
+
+pub fn user_volume_accumulator(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"user_volume_accumulator", user.as_ref()],
+        &PUMP_SWAP_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn fee_config() -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"fee_config", PUMP_SWAP_PROGRAM_ID.as_ref()],
+        &FEE_PROGRAM_ID,
+    )
+    .0
+}
+
+/// WSOL token account of `owner`; WSOL always lives under SPL Token.
+pub fn wsol_account(owner: &Pubkey) -> Pubkey {
+    pump_fun::associated_token_address(owner, &WSOL_MINT, &pump_fun::TOKEN_PROGRAM_ID)
+}
+
+/// pump.fun PDA that creates and owns the liquidity of migrated pools.
+pub fn pool_authority(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool-authority", mint.as_ref()], &PUMP_FUN_PROGRAM_ID).0
+}
+
+/// Address of the canonical WSOL pool a completed pump.fun curve migrates to.
+pub fn canonical_pool(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"pool",
+            &0u16.to_le_bytes(),
+            pool_authority(mint).as_ref(),
+            mint.as_ref(),
+            WSOL_MINT.as_ref(),
+        ],
+        &PUMP_SWAP_PROGRAM_ID,
+    )
+    .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_migration_pool_event() {
+        let mint = Pubkey::new_unique();
+        let pool = canonical_pool(&mint);
+        let lp_mint = Pubkey::new_unique();
+
+        let mut data = CREATE_POOL_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        for key in [pool_authority(&mint), mint, WSOL_MINT] {
+            data.extend_from_slice(key.as_ref());
+        }
+        data.extend_from_slice(&[6, 9]);
+        data.extend_from_slice(&206_900_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&84_990_359_107u64.to_le_bytes());
+        data.extend_from_slice(&[0; 5 * 8 + 1]);
+        data.extend_from_slice(pool.as_ref());
+        data.extend_from_slice(lp_mint.as_ref());
+        data.extend_from_slice(&[0; 2 * 32]);
+
+        let event = CreatePoolEvent::decode(&data).unwrap();
+        assert_eq!(event.base_mint, mint);
+        assert_eq!(event.pool, pool);
+        assert_eq!(event.lp_mint, lp_mint);
+        assert_eq!(event.quote_amount_in, 84_990_359_107);
+        assert!(event.is_migration());
+        assert_eq!(
+            Pubkey::find_program_address(&[b"__event_authority"], &PUMP_SWAP_PROGRAM_ID).0,
+            EVENT_AUTHORITY
+        );
+
+        let mut user_pool = event.clone();
+        user_pool.creator = Pubkey::new_unique();
+        assert!(!user_pool.is_migration());
+    }
+
+    #[test]
+    fn decodes_swap_reserves_and_pool_mints() {
+        let pool = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let coin_creator = Pubkey::new_unique();
+
+        let mut data = BUY_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&[0; 3 * 8]);
+        data.extend_from_slice(&200_000u64.to_le_bytes());
+        data.extend_from_slice(&50_000u64.to_le_bytes());
+        data.extend_from_slice(&[0; 6 * 8]);
+        data.extend_from_slice(&260u64.to_le_bytes());
+        for key in [pool, user, Pubkey::new_unique(), Pubkey::new_unique()] {
+            data.extend_from_slice(key.as_ref());
+        }
+        for key in [recipient, Pubkey::new_unique(), coin_creator] {
+            data.extend_from_slice(key.as_ref());
+        }
+
+        let swap = SwapEvent::decode(&data).unwrap();
+        assert!(swap.is_buy);
+        assert_eq!((swap.base_amount, swap.quote_amount), (1_000, 260));
+        assert_eq!(swap.reserves_after(), (199_000, 50_260));
+        assert_eq!(swap.protocol_fee_recipient, recipient);
+        assert_eq!(swap.coin_creator, coin_creator);
+
+        let mint = Pubkey::new_unique();
+        let mut account = vec![0; POOL_BASE_MINT_OFFSET];
+        account.extend_from_slice(mint.as_ref());
+        account.extend_from_slice(WSOL_MINT.as_ref());
+        assert_eq!(pool_mints(&account), Some((mint, WSOL_MINT)));
+        assert_eq!(pool_mints(&account[..60]), None);
+    }
+}
@@ -0,0 +1,327 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_sdk::{native_token::sol_to_lamports, pubkey::Pubkey, signature::Signature};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{
+    capture::Recorder,
+    dedup::EventSender,
+    launchlab, pump_fun,
+    source::{EventSource, Health, HealthHandle},
+    EventSourceKind, InitialBuy, Platform, SourceEvent, TokenEvent,
+};
+
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+pub struct PumpPortalSource {
+    endpoint: String,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+}
+
+impl PumpPortalSource {
+    pub fn new(endpoint: String, recorder: Option<Recorder>) -> Self {
+        Self {
+            endpoint,
+            health: HealthHandle::default(),
+            recorder,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for PumpPortalSource {
+    fn name(&self) -> &str {
+        "pumpportal"
+    }
+
+    async fn run(&self, tx: EventSender) -> Result<()> {
+        run(
+            self.endpoint.clone(),
+            tx,
+            self.health.clone(),
+            self.recorder.clone(),
+        )
+        .await
+    }
+
+    fn health(&self) -> Health {
+        self.health.snapshot()
+    }
+
+    fn reports_slots(&self) -> bool {
+        false
+    }
+}
+
+pub async fn run(
+    ws_endpoint: String,
+    tx: EventSender,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+) -> Result<()> {
+    log::info!("Starting PumpPortal listener at {ws_endpoint}");
+    let mut backoff = Duration::from_millis(500);
+
+    loop {
+        match connect_async(&ws_endpoint).await {
+            Ok((mut socket, _)) => {
+                log::info!("PumpPortal connected");
+                health.connected();
+                let subscribe_message =
+                    json!({ "method": "subscribeNewToken", "params": [] }).to_string();
+                let _ = socket
+                    .send(Message::text(subscribe_message))
+                    .await
+                    .map_err(|err| log::warn!("Failed to send subscribe message: {err}"));
+
+                let mut ping = tokio::time::interval(PING_INTERVAL);
+                ping.tick().await;
+
+                loop {
+                    let message = tokio::select! {
+                        message = socket.next() => message,
+                        _ = ping.tick() => {
+                            if let Err(err) = socket.send(Message::Ping(vec![])).await {
+                                log::warn!("PumpPortal ping failed: {err}");
+                                health.disconnected(&err);
+                                break;
+                            }
+                            continue;
+                        }
+                    };
+
+                    match message {
+                        Some(Ok(Message::Text(text))) => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record_text(EventSourceKind::PumpPortal, &text);
+                            }
+                            if let Some(event) = parse_event(&text) {
+                                if tx.send(SourceEvent::Token(event)).is_err() {
+                                    log::warn!("Receiver dropped, closing PumpPortal listener");
+                                    return Ok(());
+                                }
+                                health.event();
+                            }
+                        }
+                        Some(Ok(Message::Ping(data))) => {
+                            let _ = socket.send(Message::Pong(data)).await;
+                        }
+                        Some(Ok(Message::Close(frame))) => {
+                            log::warn!("PumpPortal closed: {frame:?}");
+                            health.disconnected(format!("closed: {frame:?}"));
+                            break;
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(err)) => {
+                            log::warn!("PumpPortal error: {err}");
+                            health.disconnected(&err);
+                            break;
+                        }
+                        None => {
+                            health.disconnected("stream ended");
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                log::warn!("PumpPortal connection failed: {err}");
+                health.disconnected(&err);
+            }
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff + Duration::from_millis(500)).min(Duration::from_secs(5));
+    }
+}
+
+/// Maps a PumpPortal `pool` name onto the launch platform it belongs to.
+fn platform_for_pool(pool: &str) -> Option<Platform> {
+    match pool.to_ascii_lowercase().as_str() {
+        "pump" => Some(Platform::PumpFun),
+        "bonk" => Some(Platform::LetsBonk),
+        _ => None,
+    }
+}
+
+/// Converts a PumpPortal new-token message into a `TokenEvent`. Messages
+/// arrive either bare or wrapped in a `newToken` method envelope.
+pub fn parse_event(raw: &str) -> Option<TokenEvent> {
+    let json: Value = serde_json::from_str(raw).ok()?;
+    let data = match json.get("method").and_then(Value::as_str) {
+        Some("newToken") => json.pointer("/params/0")?,
+        Some(_) => return None,
+        None => &json,
+    };
+    if data
+        .get("txType")
+        .and_then(Value::as_str)
+        .is_some_and(|kind| kind != "create")
+    {
+        return None;
+    }
+
+    let string = |field: &str| {
+        data.get(field)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let pubkey = |field: &str| Pubkey::from_str(data.get(field)?.as_str()?).ok();
+    let platform = platform_for_pool(data.get("pool")?.as_str()?)?;
+    let mint = pubkey("mint")?;
+    // PumpPortal does not distinguish the signer from the creator.
+    let developer = pubkey("traderPublicKey")?;
+    // PumpPortal doesn't report the token program. New pump.fun launches go
+    // through `create_v2`, which mints under Token-2022, but legacy `create`
+    // still mints under SPL Token, so the guess is checked before buying.
+    let (bonding_curve, associated_bonding_curve, creator_vault, token_program) = match platform {
+        Platform::PumpFun => {
+            let curve = pubkey("bondingCurveKey")?;
+            let token_program = pump_fun::TOKEN_2022_PROGRAM_ID;
+            (
+                curve,
+                pump_fun::associated_token_address(&curve, &mint, &token_program),
+                Some(pump_fun::creator_vault(&developer)),
+                token_program,
+            )
+        }
+        Platform::LetsBonk => {
+            let pool = launchlab::pool_state(&mint);
+            (
+                pool,
+                launchlab::base_vault(&pool, &mint),
+                None,
+                pump_fun::TOKEN_PROGRAM_ID,
+            )
+        }
+    };
+
+    Some(TokenEvent {
+        mint,
+        developer,
+        platform,
+        name: string("name"),
+        symbol: string("symbol"),
+        uri: string("uri"),
+        bonding_curve,
+        associated_bonding_curve,
+        creator: developer,
+        creator_vault,
+        token_program,
+        token_program_inferred: platform == Platform::PumpFun,
+        is_mayhem_mode: data
+            .get("isMayhemMode")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        source: EventSourceKind::PumpPortal,
+        slot: None,
+        signature: Signature::from_str(&string("signature")).ok(),
+        received_at: SystemTime::now(),
+        received_instant: Instant::now(),
+        backfill_age: None,
+        initial_buy: initial_buy(data),
+    })
+}
+
+/// PumpPortal reports the dev buy in whole SOL (`solAmount`) and whole
+/// 6-decimal tokens (`initialBuy`).
+fn initial_buy(data: &Value) -> Option<InitialBuy> {
+    let sol = data.get("solAmount")?.as_f64()?;
+    let tokens = data.get("initialBuy")?.as_f64()?;
+    (sol > 0.0).then(|| InitialBuy {
+        lamports: sol_to_lamports(sol),
+        tokens: (tokens * 1e6).round() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUMP_TOKEN: &str = r#"{
+        "signature": "4TqnbXdRA1zZnRZG4ujYFa6TpTGUCkmPrHAAhmp1zxdkxi4QhaQk7ZBVTgnoKmXvDSFtrZs8zcxKPPwkSSQ9tWKc",
+        "mint": "ER2N5eaDoC68kNhj7LyaScimzq7deaqxiw88rewvxaKp",
+        "traderPublicKey": "Fswrw3tgQL597kCexxLEhft6a7Su4CDoqwRMqqj4BEp5",
+        "txType": "create",
+        "initialBuy": 65511482.57,
+        "solAmount": 2,
+        "bondingCurveKey": "FFzxakVNzpirwMFtLyD22UZ6UM3KLF2EAGC2RxNPaYoH",
+        "vTokensInBondingCurve": 1007488517.43,
+        "vSolInBondingCurve": 31.95,
+        "marketCapSol": 31.71,
+        "name": "excited",
+        "symbol": "excited",
+        "uri": "https://cf-ipfs.com/ipfs/QmcDiP9wAZ8QeijrNrtwLeMGk46vKAAe8yvfWtumBZbURq",
+        "pool": "pump"
+    }"#;
+
+    #[test]
+    fn parses_pump_token() {
+        let event = parse_event(PUMP_TOKEN).unwrap();
+        assert_eq!(event.platform, Platform::PumpFun);
+        assert_eq!(
+            event.mint,
+            Pubkey::from_str("ER2N5eaDoC68kNhj7LyaScimzq7deaqxiw88rewvxaKp").unwrap()
+        );
+        assert_eq!(
+            event.bonding_curve,
+            Pubkey::from_str("FFzxakVNzpirwMFtLyD22UZ6UM3KLF2EAGC2RxNPaYoH").unwrap()
+        );
+        assert_eq!(event.creator, event.developer);
+        assert_eq!(event.symbol, "excited");
+        assert_eq!(
+            event.initial_buy,
+            Some(InitialBuy {
+                lamports: 2_000_000_000,
+                tokens: 65_511_482_570_000,
+            })
+        );
+        assert!(event.signature.is_some());
+        assert_eq!(event.token_program, pump_fun::TOKEN_2022_PROGRAM_ID);
+        assert!(event.token_program_inferred);
+
+        // A legacy create turns out to be minted under SPL Token.
+        let event = event.with_token_program(pump_fun::TOKEN_PROGRAM_ID);
+        assert_eq!(event.token_program, pump_fun::TOKEN_PROGRAM_ID);
+        assert_eq!(
+            event.associated_bonding_curve,
+            pump_fun::associated_token_address(
+                &event.bonding_curve,
+                &event.mint,
+                &pump_fun::TOKEN_PROGRAM_ID
+            )
+        );
+        assert!(!event.token_program_inferred);
+    }
+
+    #[test]
+    fn maps_bonk_pool_to_launchlab_state() {
+        let mut json: Value = serde_json::from_str(PUMP_TOKEN).unwrap();
+        json["pool"] = json!("bonk");
+        json.as_object_mut().unwrap().remove("bondingCurveKey");
+        let wrapped = json!({ "method": "newToken", "params": [json] }).to_string();
+
+        let event = parse_event(&wrapped).unwrap();
+        assert_eq!(event.platform, Platform::LetsBonk);
+        assert_eq!(event.bonding_curve, launchlab::pool_state(&event.mint));
+    }
+
+    #[test]
+    fn ignores_unknown_pools_and_acks() {
+        let mut json: Value = serde_json::from_str(PUMP_TOKEN).unwrap();
+        json["pool"] = json!("moonshot");
+        assert!(parse_event(&json.to_string()).is_none());
+        assert!(
+            parse_event(r#"{"message":"Successfully subscribed to token creation events."}"#)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+use tokio::sync::Notify;
+
+use super::SourceEvent;
+use crate::config::EventQueueConfig;
+
+/// What the queue sheds besides overflow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    /// Only shed the oldest events once the queue is full.
+    #[default]
+    DropOldest,
+    /// Also shed creates older than `max_token_age_ms` when they come up.
+    MaxAge,
+}
+
+/// Counters of what the queue dropped, shared with readers.
+#[derive(Clone, Debug, Default)]
+pub struct QueueStats {
+    inner: Arc<QueueCounters>,
+}
+
+#[derive(Debug, Default)]
+struct QueueCounters {
+    depth: AtomicUsize,
+    overflow: AtomicU64,
+    stale: AtomicU64,
+}
+
+impl QueueStats {
+    pub fn depth(&self) -> usize {
+        self.inner.depth.load(Ordering::Relaxed)
+    }
+
+    /// Events dropped because the queue was full.
+    pub fn overflow(&self) -> u64 {
+        self.inner.overflow.load(Ordering::Relaxed)
+    }
+
+    /// Creates dropped for being older than the age limit.
+    pub fn stale(&self) -> u64 {
+        self.inner.stale.load(Ordering::Relaxed)
+    }
+}
+
+/// Creates and copy trades are dequeued first and shed last.
+#[derive(Default)]
+struct Lanes {
+    high: VecDeque<SourceEvent>,
+    low: VecDeque<SourceEvent>,
+}
+
+impl Lanes {
+    fn len(&self) -> usize {
+        self.high.len() + self.low.len()
+    }
+}
+
+struct Shared {
+    lanes: Mutex<Lanes>,
+    notify: Notify,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+    capacity: usize,
+    max_age: Option<Duration>,
+    stats: QueueStats,
+}
+
+/// Creates the bounded queue between the fan-in stage and the pipeline.
+pub fn channel(config: &EventQueueConfig, stats: QueueStats) -> (QueueSender, QueueReceiver) {
+    let max_age = (config.policy() == DropPolicy::MaxAge).then(|| config.max_token_age());
+    let shared = Arc::new(Shared {
+        lanes: Mutex::new(Lanes::default()),
+        notify: Notify::new(),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+        capacity: config.capacity(),
+        max_age,
+        stats,
+    });
+    (
+        QueueSender {
+            shared: shared.clone(),
+        },
+        QueueReceiver { shared },
+    )
+}
+
+/// The receiving half was dropped.
+#[derive(Debug)]
+pub struct QueueClosed;
+
+pub struct QueueSender {
+    shared: Arc<Shared>,
+}
+
+impl QueueSender {
+    pub fn is_closed(&self) -> bool {
+        !self.shared.receiver_alive.load(Ordering::Acquire)
+    }
+
+    /// Enqueues `event`, shedding the oldest low-priority event (or the
+    /// oldest event, if there is none) when full. Fails once the receiver is
+    /// gone.
+    pub fn send(&self, event: SourceEvent) -> Result<(), QueueClosed> {
+        let shared = &self.shared;
+        if !shared.receiver_alive.load(Ordering::Acquire) {
+            return Err(QueueClosed);
+        }
+        {
+            let mut lanes = shared.lanes.lock();
+            let full = lanes.len() >= shared.capacity;
+            if full
+                && lanes
+                    .low
+                    .pop_front()
+                    .or_else(|| lanes.high.pop_front())
+                    .is_some()
+            {
+                shared.stats.inner.overflow.fetch_add(1, Ordering::Relaxed);
+            }
+            match event {
+                SourceEvent::Token(_) | SourceEvent::CopyTrade(_) => lanes.high.push_back(event),
+                SourceEvent::Trade(_) | SourceEvent::Migration(_) => lanes.low.push_back(event),
+            }
+            shared
+                .stats
+                .inner
+                .depth
+                .store(lanes.len(), Ordering::Relaxed);
+        }
+        shared.notify.notify_one();
+        Ok(())
+    }
+}
+
+impl Clone for QueueSender {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::AcqRel);
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for QueueSender {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+pub struct QueueReceiver {
+    shared: Arc<Shared>,
+}
+
+impl QueueReceiver {
+    /// Next event, creates and copy trades first. Returns `None` once every
+    /// sender is gone and the queue is drained.
+    pub async fn recv(&mut self) -> Option<SourceEvent> {
+        loop {
+            if let Some(event) = self.try_recv() {
+                return Some(event);
+            }
+            if self.shared.senders.load(Ordering::Acquire) == 0 {
+                return self.try_recv();
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+
+    fn try_recv(&self) -> Option<SourceEvent> {
+        let shared = &self.shared;
+        let mut lanes = shared.lanes.lock();
+        let event = loop {
+            let event = lanes.high.pop_front().or_else(|| lanes.low.pop_front())?;
+            let stale = match (&event, shared.max_age) {
+                (SourceEvent::Token(token), Some(max_age)) => {
+                    token.received_instant.elapsed() > max_age
+                }
+                _ => false,
+            };
+            if !stale {
+                break event;
+            }
+            shared.stats.inner.stale.fetch_add(1, Ordering::Relaxed);
+        };
+        shared
+            .stats
+            .inner
+            .depth
+            .store(lanes.len(), Ordering::Relaxed);
+        Some(event)
+    }
+}
+
+impl Drop for QueueReceiver {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Instant, SystemTime};
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+    use crate::events::{pump_fun, EventSourceKind, TokenEvent, TradeUpdate};
+
+    fn token(received_instant: Instant) -> SourceEvent {
+        SourceEvent::Token(TokenEvent {
+            received_instant,
+            ..TokenEvent::for_test(Pubkey::new_unique(), Pubkey::new_unique())
+        })
+    }
+
+    fn trade(ordinal: u32) -> SourceEvent {
+        SourceEvent::Trade(TradeUpdate {
+            trade: pump_fun::TradeEvent::for_test(Pubkey::new_unique(), Pubkey::new_unique(), true),
+            source: EventSourceKind::WebSocket,
+            slot: None,
+            signature: None,
+            ordinal,
+            received_at: SystemTime::now(),
+        })
+    }
+
+    fn config(capacity: usize, policy: DropPolicy) -> EventQueueConfig {
+        EventQueueConfig {
+            capacity: Some(capacity),
+            policy: Some(policy),
+            max_token_age_ms: Some(1_000),
+        }
+    }
+
+    #[tokio::test]
+    async fn sheds_trades_before_creates_when_full() {
+        let stats = QueueStats::default();
+        let (tx, mut rx) = channel(&config(3, DropPolicy::DropOldest), stats.clone());
+
+        tx.send(trade(0)).unwrap();
+        tx.send(token(Instant::now())).unwrap();
+        tx.send(trade(1)).unwrap();
+        tx.send(token(Instant::now())).unwrap();
+        assert_eq!(stats.overflow(), 1);
+        assert_eq!(stats.depth(), 3);
+
+        assert!(matches!(rx.recv().await, Some(SourceEvent::Token(_))));
+        assert!(matches!(rx.recv().await, Some(SourceEvent::Token(_))));
+        assert!(matches!(rx.recv().await, Some(SourceEvent::Trade(update)) if update.ordinal == 1));
+        drop(tx);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn sheds_creates_past_max_age() {
+        let stats = QueueStats::default();
+        let (tx, mut rx) = channel(&config(8, DropPolicy::MaxAge), stats.clone());
+        let old = Instant::now() - Duration::from_secs(5);
+
+        tx.send(token(old)).unwrap();
+        tx.send(token(Instant::now())).unwrap();
+        let SourceEvent::Token(event) = rx.recv().await.unwrap() else {
+            panic!("expected a create");
+        };
+        assert!(event.received_instant > old);
+        assert_eq!(stats.stale(), 1);
+
+        drop(rx);
+        assert!(tx.send(trade(0)).is_err());
+    }
+}
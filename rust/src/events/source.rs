@@ -0,0 +1,159 @@
+use std::{fmt::Display, sync::Arc, time::SystemTime};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+use super::{
+    block,
+    capture::{Recorder, ReplaySource},
+    copytrade,
+    dedup::EventSender,
+    laserstream, pumpportal, websocket,
+    ws_pool::EndpointScore,
+};
+use crate::config::Config;
+
+/// A feed of new-token events. Implementations own their reconnect loop and
+/// return `Ok(())` only once the receiver is gone; an `Err` or panic makes
+/// the supervisor restart them.
+#[async_trait]
+pub trait EventSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn run(&self, tx: EventSender) -> Result<()>;
+
+    fn health(&self) -> Health;
+
+    /// Whether the creates and trades it delivers carry their slot, which
+    /// bundle checks need.
+    fn reports_slots(&self) -> bool {
+        true
+    }
+
+    /// Per-endpoint scores for sources that pool several endpoints.
+    fn endpoints(&self) -> Vec<EndpointScore> {
+        Vec::new()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Health {
+    pub connected: bool,
+    pub events: u64,
+    pub last_event: Option<SystemTime>,
+    pub last_error: Option<String>,
+    /// Filled in by the supervisor, which is what restarts sources.
+    pub restarts: u32,
+}
+
+/// Shared health record a source updates while it runs.
+#[derive(Clone, Debug, Default)]
+pub struct HealthHandle(Arc<Mutex<Health>>);
+
+impl HealthHandle {
+    pub fn connected(&self) {
+        self.0.lock().connected = true;
+    }
+
+    pub fn disconnected(&self, reason: impl Display) {
+        let mut health = self.0.lock();
+        health.connected = false;
+        health.last_error = Some(reason.to_string());
+    }
+
+    pub fn event(&self) {
+        let mut health = self.0.lock();
+        health.events += 1;
+        health.last_event = Some(SystemTime::now());
+    }
+
+    pub fn snapshot(&self) -> Health {
+        self.0.lock().clone()
+    }
+}
+
+/// The set of sources the supervisor runs.
+#[derive(Clone, Default)]
+pub struct SourceRegistry {
+    sources: Vec<Arc<dyn EventSource>>,
+}
+
+impl SourceRegistry {
+    /// Registers every source that has an endpoint configured, plus the
+    /// copy-trading source when `copy_trade` is set, or only a replay source
+    /// when `capture.replay_path` is set.
+    pub fn from_config(config: &Arc<Config>) -> Result<Self> {
+        let endpoints = &config.endpoints;
+        let mut registry = Self::default();
+
+        if let Some(path) = &config.capture.replay_path {
+            let speed = config.capture.replay_speed.unwrap_or(1.0);
+            registry.register(ReplaySource::new(path, config.program_id()?, speed));
+            return Ok(registry);
+        }
+        let recorder = config
+            .capture
+            .record_path
+            .as_ref()
+            .map(Recorder::create)
+            .transpose()?;
+
+        if let Some(endpoint) = &endpoints.laserstream_grpc_url {
+            registry.register(laserstream::LaserStreamSource::new(
+                endpoint.clone(),
+                config.clone(),
+                recorder.clone(),
+            ));
+        }
+        if let Some(endpoint) = &endpoints.block_ws_url {
+            registry.register(block::BlockSource::new(
+                endpoint.clone(),
+                config.clone(),
+                recorder.clone(),
+            ));
+        }
+        if let Some(endpoint) = &endpoints.pumpportal_ws_url {
+            registry.register(pumpportal::PumpPortalSource::new(
+                endpoint.clone(),
+                recorder.clone(),
+            ));
+        }
+        let ws_endpoints = config.ws_endpoints();
+        if !ws_endpoints.is_empty() {
+            registry.register(websocket::WebSocketSource::new(
+                ws_endpoints.clone(),
+                config.clone(),
+                recorder.clone(),
+            ));
+        }
+        if let Some(copy) = &config.copy_trade {
+            let leaders = config.copy_leaders()?;
+            match copy.ws_url.as_ref().or(ws_endpoints.first()) {
+                Some(endpoint) if !leaders.is_empty() => {
+                    registry.register(copytrade::CopyTradeSource::new(
+                        endpoint.clone(),
+                        config.program_id()?,
+                        leaders,
+                        recorder.clone(),
+                    ));
+                }
+                Some(_) => log::warn!("copy_trade has no leaders; not tracking wallets"),
+                None => log::warn!("copy_trade needs a ws_url; not tracking wallets"),
+            }
+        }
+
+        if config.bundles.is_some() && !registry.sources.iter().any(|s| s.reports_slots()) {
+            log::warn!("[bundles] is set but no source reports slots; launches won't be checked");
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, source: impl EventSource + 'static) {
+        self.sources.push(Arc::new(source));
+    }
+
+    pub fn sources(&self) -> &[Arc<dyn EventSource>] {
+        &self.sources
+    }
+}
@@ -1,109 +1,359 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use futures::{SinkExt, StreamExt};
-codex/convert-pump.fun-sniper-bot-to-rust
 use serde_json::{json, Value};
-=======
-use serde_json::Value;
-main
-use solana_sdk::pubkey::Pubkey;
-use std::str::FromStr;
-use tokio::sync::mpsc::UnboundedSender;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio::task::JoinSet;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message;
 
-use super::{EventSourceKind, TokenEvent};
+use super::{
+    backfill,
+    capture::Recorder,
+    dedup::EventSender,
+    pump_fun,
+    source::{EventSource, Health, HealthHandle},
+    ws_pool::{EndpointScore, WsPool},
+    EventSourceKind, SourceEvent,
+};
 use crate::config::Config;
 
+/// How often the pool is checked for endpoints to rotate out.
+const ROTATION_CHECK: Duration = Duration::from_secs(1);
+
+/// `logsSubscribe` over a pool of endpoints, several connected at once.
+pub struct WebSocketSource {
+    pool: WsPool,
+    config: Arc<Config>,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+}
+
+impl WebSocketSource {
+    pub fn new(endpoints: Vec<String>, config: Arc<Config>, recorder: Option<Recorder>) -> Self {
+        Self {
+            pool: WsPool::new(endpoints, config.ws_pool.clone()),
+            config,
+            health: HealthHandle::default(),
+            recorder,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSource for WebSocketSource {
+    fn name(&self) -> &str {
+        "websocket"
+    }
+
+    async fn run(&self, tx: EventSender) -> Result<()> {
+        run(
+            self.pool.clone(),
+            self.config.clone(),
+            tx,
+            self.health.clone(),
+            self.recorder.clone(),
+        )
+        .await
+    }
+
+    fn health(&self) -> Health {
+        self.health.snapshot()
+    }
+
+    fn endpoints(&self) -> Vec<EndpointScore> {
+        self.pool.scores()
+    }
+}
+
+/// Connects the pool's active endpoints and swaps degraded ones for standbys
+/// until the receiver is dropped.
 pub async fn run(
-    ws_endpoint: String,
-codex/convert-pump.fun-sniper-bot-to-rust
+    pool: WsPool,
     config: Arc<Config>,
-=======
-    _config: Arc<Config>,
-main
-    tx: UnboundedSender<TokenEvent>,
+    tx: EventSender,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
 ) -> Result<()> {
-    log::info!("Starting websocket listener at {ws_endpoint}");
-    let mut backoff = Duration::from_millis(500);
- codex/convert-pump.fun-sniper-bot-to-rust
-    let program_id = config.program_id()?.to_string();
-=======
- main
+    let program = config.program_id()?;
+    let rpc = Arc::new(RpcClient::new(config.endpoints.rpc_http_url.clone()));
+    let connection = |index| Connection {
+        index,
+        pool: pool.clone(),
+        program,
+        rpc: rpc.clone(),
+        tx: tx.clone(),
+        health: health.clone(),
+        recorder: recorder.clone(),
+    };
+
+    let mut connections = JoinSet::new();
+    let mut handles = HashMap::new();
+    for index in pool.active() {
+        handles.insert(index, connections.spawn(connection(index).run()));
+    }
 
+    let mut check = tokio::time::interval(ROTATION_CHECK);
     loop {
-        match connect_async(&ws_endpoint).await {
-            Ok((mut socket, _)) => {
-                log::info!("WebSocket connected");
- codex/convert-pump.fun-sniper-bot-to-rust
-                let subscribe_message = json!({
-                    "jsonrpc": "2.0",
-                    "id": 1,
-                    "method": "logsSubscribe",
-                    "params": [
-                        { "mentions": [program_id.clone()] },
-                        { "commitment": "processed" }
-                    ]
-                })
-                .to_string();
-
-                let _ = socket
-                    .send(Message::text(subscribe_message))
-=======
-                let _ = socket
-                    .send(Message::text("{}"))
- main
-                    .await
-                    .map_err(|err| log::warn!("Failed to send subscribe message: {err}"));
-
-                while let Some(message) = socket.next().await {
-                    match message {
-                        Ok(Message::Text(text)) => {
-                            if let Some(event) = parse_event(&text) {
-                                if tx.send(event).is_err() {
-                                    log::warn!("Receiver dropped, closing websocket listener");
-                                    return Ok(());
+        tokio::select! {
+            Some(joined) = connections.join_next() => match joined {
+                // Connections only return once the receiver is gone.
+                Ok(()) => return Ok(()),
+                Err(err) if err.is_cancelled() => {}
+                Err(err) => return Err(anyhow!("websocket connection crashed: {err}")),
+            },
+            _ = check.tick() => {
+                let Some((out, replacement)) = pool.rotate(Instant::now()) else {
+                    continue;
+                };
+                log::warn!(
+                    "Rotating out degraded WebSocket endpoint {}; switching to {}",
+                    pool.endpoint(out),
+                    pool.endpoint(replacement)
+                );
+                if let Some(handle) = handles.remove(&out) {
+                    handle.abort();
+                }
+                handles.insert(replacement, connections.spawn(connection(replacement).run()));
+            }
+        }
+    }
+}
+
+/// One pooled endpoint's reconnect loop.
+struct Connection {
+    index: usize,
+    pool: WsPool,
+    program: Pubkey,
+    rpc: Arc<RpcClient>,
+    tx: EventSender,
+    health: HealthHandle,
+    recorder: Option<Recorder>,
+}
+
+impl Connection {
+    async fn run(self) {
+        let ws_endpoint = self.pool.endpoint(self.index);
+        log::info!("Starting websocket listener at {ws_endpoint}");
+        let mut backoff = Duration::from_millis(500);
+        let program_id = self.program.to_string();
+
+        loop {
+            match connect_async(&ws_endpoint).await {
+                Ok((mut socket, _)) => {
+                    log::info!("WebSocket connected to {ws_endpoint}");
+                    self.health.connected();
+                    if let Some(from_slot) = self.pool.connected(self.index) {
+                        backfill::spawn(
+                            self.rpc.clone(),
+                            self.program,
+                            from_slot,
+                            EventSourceKind::WebSocket,
+                            self.tx.clone(),
+                            self.health.clone(),
+                        );
+                    }
+                    let subscribe_message = json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "method": "logsSubscribe",
+                        "params": [
+                            { "mentions": [program_id.clone()] },
+                            { "commitment": "processed" }
+                        ]
+                    })
+                    .to_string();
+
+                    let _ = socket
+                        .send(Message::text(subscribe_message))
+                        .await
+                        .map_err(|err| log::warn!("Failed to send subscribe message: {err}"));
+
+                    while let Some(message) = socket.next().await {
+                        match message {
+                            Ok(Message::Text(text)) => {
+                                if let Some(recorder) = &self.recorder {
+                                    recorder.record_text(EventSourceKind::WebSocket, &text);
+                                }
+                                let Some(notification) = LogsNotification::parse(&text) else {
+                                    continue;
+                                };
+                                let first = self.pool.message(
+                                    self.index,
+                                    notification.signature,
+                                    notification.slot,
+                                    Instant::now(),
+                                );
+                                if !first {
+                                    continue;
+                                }
+                                for event in notification.into_events(&self.program) {
+                                    if self.tx.send(event).is_err() {
+                                        log::warn!("Receiver dropped, closing websocket listener");
+                                        return;
+                                    }
+                                    self.health.event();
                                 }
                             }
-                        }
-                        Ok(Message::Binary(_)) => {}
-                        Ok(Message::Pong(_)) | Ok(Message::Frame(_)) => {}
-                        Ok(Message::Ping(data)) => {
-                            let _ = socket.send(Message::Pong(data)).await;
-                        }
-                        Ok(Message::Close(frame)) => {
-                            log::warn!("WebSocket closed: {frame:?}");
-                            break;
-                        }
-                        Err(err) => {
-                            log::warn!("WebSocket error: {err}");
-                            break;
+                            Ok(Message::Binary(_)) => {}
+                            Ok(Message::Pong(_)) | Ok(Message::Frame(_)) => {}
+                            Ok(Message::Ping(data)) => {
+                                let _ = socket.send(Message::Pong(data)).await;
+                            }
+                            Ok(Message::Close(frame)) => {
+                                log::warn!("WebSocket {ws_endpoint} closed: {frame:?}");
+                                self.disconnected(format!("closed: {frame:?}"));
+                                break;
+                            }
+                            Err(err) => {
+                                log::warn!("WebSocket {ws_endpoint} error: {err}");
+                                self.disconnected(&err);
+                                break;
+                            }
                         }
                     }
                 }
+                Err(err) => {
+                    log::warn!("WebSocket connection to {ws_endpoint} failed: {err}");
+                    self.disconnected(&err);
+                }
             }
-            Err(err) => log::warn!("WebSocket connection failed: {err}"),
+            if self.tx.is_closed() {
+                return;
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff + Duration::from_millis(500)).min(Duration::from_secs(5));
         }
+    }
 
-        tokio::time::sleep(backoff).await;
-        backoff = (backoff + Duration::from_millis(500)).min(Duration::from_secs(5));
+    /// The source only reports down once no pooled endpoint is connected.
+    fn disconnected(&self, reason: impl Display) {
+        if !self.pool.disconnected(self.index, Instant::now()) {
+            self.health.disconnected(reason);
+        }
     }
 }
 
-fn parse_event(raw: &str) -> Option<TokenEvent> {
-    let json: Value = serde_json::from_str(raw).ok()?;
-    let params = json.get("params")?.get("result")?.get("value")?;
-    let dev_str = params.get("developer")?.as_str()?;
-    let mint_str = params.get("mint")?.as_str()?;
+/// The parts of a `logsNotification` the parsers use.
+pub struct LogsNotification {
+    pub slot: Option<u64>,
+    pub signature: Option<Signature>,
+    pub logs: Vec<String>,
+}
+
+impl LogsNotification {
+    /// Returns `None` for other messages and for failed transactions.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let json = serde_json::from_str::<Value>(raw).ok()?;
+        let value = json.pointer("/params/result/value")?;
+        if value.get("err").is_some_and(|err| !err.is_null()) {
+            return None;
+        }
+        let logs = value
+            .get("logs")?
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect();
+        let slot = json
+            .pointer("/params/result/context/slot")
+            .and_then(Value::as_u64);
+        let signature = value
+            .get("signature")
+            .and_then(Value::as_str)
+            .and_then(|signature| Signature::from_str(signature).ok());
 
-    let developer = Pubkey::from_str(dev_str).ok()?;
-    let mint = Pubkey::from_str(mint_str).ok()?;
+        Some(Self {
+            slot,
+            signature,
+            logs,
+        })
+    }
 
-    Some(TokenEvent {
-        mint,
-        developer,
-        source: EventSourceKind::WebSocket,
-    })
+    pub fn into_events(self, program: &Pubkey) -> Vec<SourceEvent> {
+        pump_fun::source_events_from_logs(
+            &self.logs,
+            program,
+            EventSourceKind::WebSocket,
+            self.slot,
+            self.signature,
+        )
+    }
+}
+
+/// Decodes pump.fun `CreateEvent`s and `TradeEvent`s from a
+/// `logsNotification` payload.
+pub fn parse_events(raw: &str, program: &Pubkey) -> Vec<SourceEvent> {
+    LogsNotification::parse(raw)
+        .map_or_else(Vec::new, |notification| notification.into_events(program))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::events::{pump_fun::PUMP_FUN_PROGRAM_ID, TokenEvent};
+
+    const CREATE_TX: &str =
+        include_str!("../../../learning-examples/raw_create_tx_from_getTransaction.json");
+
+    fn logs_notification(err: Value) -> String {
+        let tx: Value = serde_json::from_str(CREATE_TX).unwrap();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "logsNotification",
+            "params": {
+                "result": {
+                    "context": { "slot": tx["result"]["slot"] },
+                    "value": {
+                        "signature": tx["result"]["transaction"]["signatures"][0],
+                        "err": err,
+                        "logs": tx["result"]["meta"]["logMessages"],
+                    }
+                },
+                "subscription": 1
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn parses_create_from_logs_notification() {
+        let events = parse_events(&logs_notification(Value::Null), &PUMP_FUN_PROGRAM_ID);
+        // The create is followed by the developer's initial buy.
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[1], SourceEvent::Trade(update) if update.trade.is_buy));
+        let events: Vec<TokenEvent> = events
+            .into_iter()
+            .filter_map(SourceEvent::into_token)
+            .collect();
+        assert_eq!(
+            events[0].mint,
+            Pubkey::from_str("ER2N5eaDoC68kNhj7LyaScimzq7deaqxiw88rewvxaKp").unwrap()
+        );
+        assert_eq!(
+            events[0].developer,
+            Pubkey::from_str("Fswrw3tgQL597kCexxLEhft6a7Su4CDoqwRMqqj4BEp5").unwrap()
+        );
+        assert_eq!(events[0].slot, Some(284682639));
+    }
+
+    #[test]
+    fn skips_failed_transactions_and_rpc_responses() {
+        let failed = logs_notification(json!({ "InstructionError": [3, "Custom"] }));
+        assert!(parse_events(&failed, &PUMP_FUN_PROGRAM_ID).is_empty());
+
+        let ack = r#"{"jsonrpc":"2.0","result":42,"id":1}"#;
+        assert!(parse_events(ack, &PUMP_FUN_PROGRAM_ID).is_empty());
+    }
 }
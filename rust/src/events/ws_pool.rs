@@ -0,0 +1,347 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use parking_lot::Mutex;
+use solana_sdk::signature::Signature;
+
+use crate::config::WsPoolConfig;
+
+/// Window over which disconnects count against an endpoint.
+const DISCONNECT_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// How long a rotated-out endpoint sits out before it can be picked again.
+const BENCH_TIME: Duration = Duration::from_secs(5 * 60);
+/// First arrivals are remembered this long to time the slower endpoints.
+const FIRST_SEEN_WINDOW: Duration = Duration::from_secs(30);
+/// Weight of each new sample in the lag moving average.
+const LAG_ALPHA: f64 = 0.1;
+/// Score penalties, in lag milliseconds, for each recent disconnect and for
+/// being stale.
+const DISCONNECT_PENALTY_MS: f64 = 100.0;
+const STALE_PENALTY_MS: f64 = 10_000.0;
+
+/// One pooled endpoint's standing, for ops. Lower scores are better.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndpointScore {
+    pub endpoint: String,
+    pub active: bool,
+    pub connected: bool,
+    pub messages: u64,
+    /// Moving average of how far behind the fastest pooled endpoint this one
+    /// delivers the same transaction.
+    pub mean_lag_ms: f64,
+    /// Disconnects in the last hour.
+    pub disconnects: u32,
+    pub last_message: Option<SystemTime>,
+    pub stale: bool,
+    pub score: f64,
+}
+
+#[derive(Debug)]
+struct EndpointHealth {
+    endpoint: String,
+    active: bool,
+    connected: bool,
+    activated_at: Instant,
+    benched_until: Option<Instant>,
+    messages: u64,
+    mean_lag_ms: f64,
+    disconnects: VecDeque<Instant>,
+    last_message: Option<(Instant, SystemTime)>,
+}
+
+impl EndpointHealth {
+    fn idle_for(&self, now: Instant) -> Duration {
+        let since = self
+            .last_message
+            .map_or(self.activated_at, |(at, _)| at.max(self.activated_at));
+        now.saturating_duration_since(since)
+    }
+
+    fn recent_disconnects(&self, now: Instant) -> u32 {
+        self.disconnects
+            .iter()
+            .filter(|at| now.saturating_duration_since(**at) <= DISCONNECT_WINDOW)
+            .count() as u32
+    }
+
+    fn score(&self, now: Instant, config: &WsPoolConfig) -> EndpointScore {
+        let disconnects = self.recent_disconnects(now);
+        let stale = self.active && self.idle_for(now) > config.stale_after();
+        let mut score = self.mean_lag_ms + DISCONNECT_PENALTY_MS * disconnects as f64;
+        if stale {
+            score += STALE_PENALTY_MS;
+        }
+        EndpointScore {
+            endpoint: self.endpoint.clone(),
+            active: self.active,
+            connected: self.connected,
+            messages: self.messages,
+            mean_lag_ms: self.mean_lag_ms,
+            disconnects,
+            last_message: self.last_message.map(|(_, at)| at),
+            stale,
+            score,
+        }
+    }
+
+    fn is_degraded(&self, now: Instant, config: &WsPoolConfig) -> bool {
+        self.idle_for(now) > config.stale_after()
+            || self.mean_lag_ms > config.max_lag().as_millis() as f64
+            || self.recent_disconnects(now) > config.max_disconnects_per_hour()
+    }
+}
+
+struct PoolInner {
+    endpoints: Vec<EndpointHealth>,
+    first_seen: HashMap<Signature, Instant>,
+    /// Newest slot any pooled endpoint delivered.
+    last_slot: Option<u64>,
+}
+
+/// Shared scoring for the WebSocket source's endpoints. Each connection
+/// reports into it; the source asks it which endpoint to rotate out.
+#[derive(Clone)]
+pub struct WsPool {
+    config: WsPoolConfig,
+    inner: Arc<Mutex<PoolInner>>,
+}
+
+impl WsPool {
+    /// The first `config.connections()` endpoints start active.
+    pub fn new(endpoints: Vec<String>, config: WsPoolConfig) -> Self {
+        let now = Instant::now();
+        let active = config.connections();
+        let endpoints = endpoints
+            .into_iter()
+            .enumerate()
+            .map(|(index, endpoint)| EndpointHealth {
+                endpoint,
+                active: index < active,
+                connected: false,
+                activated_at: now,
+                benched_until: None,
+                messages: 0,
+                mean_lag_ms: 0.0,
+                disconnects: VecDeque::new(),
+                last_message: None,
+            })
+            .collect();
+        Self {
+            config,
+            inner: Arc::new(Mutex::new(PoolInner {
+                endpoints,
+                first_seen: HashMap::new(),
+                last_slot: None,
+            })),
+        }
+    }
+
+    pub fn endpoint(&self, index: usize) -> String {
+        self.inner.lock().endpoints[index].endpoint.clone()
+    }
+
+    pub fn active(&self) -> Vec<usize> {
+        let inner = self.inner.lock();
+        (0..inner.endpoints.len())
+            .filter(|index| inner.endpoints[*index].active)
+            .collect()
+    }
+
+    /// Marks `index` connected. Returns the slot to backfill from when no
+    /// other endpoint stayed connected through the gap.
+    pub fn connected(&self, index: usize) -> Option<u64> {
+        let mut inner = self.inner.lock();
+        let covered = inner.endpoints.iter().any(|endpoint| endpoint.connected);
+        inner.endpoints[index].connected = true;
+        if covered {
+            return None;
+        }
+        inner.last_slot
+    }
+
+    /// Marks `index` disconnected. Returns whether any endpoint is still up.
+    pub fn disconnected(&self, index: usize, now: Instant) -> bool {
+        let mut inner = self.inner.lock();
+        let endpoint = &mut inner.endpoints[index];
+        if endpoint.connected {
+            endpoint.disconnects.push_back(now);
+        }
+        endpoint.connected = false;
+        inner.endpoints.iter().any(|endpoint| endpoint.connected)
+    }
+
+    /// Records a notification from `index`. Returns `false` when another
+    /// endpoint already delivered the same transaction.
+    pub fn message(
+        &self,
+        index: usize,
+        signature: Option<Signature>,
+        slot: Option<u64>,
+        now: Instant,
+    ) -> bool {
+        let mut inner = self.inner.lock();
+        inner.last_slot = inner.last_slot.max(slot);
+        let lag = match signature {
+            Some(signature) => match inner.first_seen.get(&signature) {
+                Some(first) => Some(now.saturating_duration_since(*first)),
+                None => {
+                    inner.first_seen.insert(signature, now);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let endpoint = &mut inner.endpoints[index];
+        endpoint.messages += 1;
+        endpoint.last_message = Some((now, SystemTime::now()));
+        let sample = lag.unwrap_or_default().as_secs_f64() * 1_000.0;
+        endpoint.mean_lag_ms += LAG_ALPHA * (sample - endpoint.mean_lag_ms);
+        lag.is_none()
+    }
+
+    /// Picks a degraded active endpoint and the best rested standby to
+    /// replace it, and swaps their roles. Returns `(out, in)`.
+    pub fn rotate(&self, now: Instant) -> Option<(usize, usize)> {
+        let mut inner = self.inner.lock();
+        inner
+            .first_seen
+            .retain(|_, at| now.saturating_duration_since(*at) <= FIRST_SEEN_WINDOW);
+        for endpoint in &mut inner.endpoints {
+            while endpoint
+                .disconnects
+                .front()
+                .is_some_and(|at| now.saturating_duration_since(*at) > DISCONNECT_WINDOW)
+            {
+                endpoint.disconnects.pop_front();
+            }
+        }
+
+        let config = &self.config;
+        let endpoints = &inner.endpoints;
+        let out = (0..endpoints.len())
+            .filter(|index| endpoints[*index].active)
+            .filter(|index| endpoints[*index].is_degraded(now, config))
+            .max_by(|a, b| {
+                let a = endpoints[*a].score(now, config).score;
+                let b = endpoints[*b].score(now, config).score;
+                a.total_cmp(&b)
+            })?;
+        let replacement = (0..endpoints.len())
+            .filter(|index| !endpoints[*index].active)
+            .filter(|index| {
+                endpoints[*index]
+                    .benched_until
+                    .is_none_or(|until| until <= now)
+            })
+            .min_by(|a, b| {
+                let a = endpoints[*a].score(now, config).score;
+                let b = endpoints[*b].score(now, config).score;
+                a.total_cmp(&b)
+            })?;
+
+        let endpoints = &mut inner.endpoints;
+        endpoints[out].active = false;
+        endpoints[out].connected = false;
+        endpoints[out].benched_until = Some(now + BENCH_TIME);
+        endpoints[replacement].active = true;
+        endpoints[replacement].activated_at = now;
+        endpoints[replacement].mean_lag_ms = 0.0;
+        Some((out, replacement))
+    }
+
+    pub fn scores(&self) -> Vec<EndpointScore> {
+        let now = Instant::now();
+        self.inner
+            .lock()
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.score(now, &self.config))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(endpoints: usize) -> WsPool {
+        let config = WsPoolConfig {
+            connections: Some(2),
+            stale_after_secs: Some(10),
+            max_lag_ms: Some(200),
+            max_disconnects_per_hour: Some(2),
+        };
+        WsPool::new(
+            (0..endpoints)
+                .map(|index| format!("wss://{index}"))
+                .collect(),
+            config,
+        )
+    }
+
+    #[test]
+    fn scores_lag_behind_first_arrival() {
+        let pool = pool(2);
+        let start = Instant::now();
+        pool.connected(0);
+        pool.connected(1);
+
+        for offset in 0..50 {
+            let signature = Signature::new_unique();
+            let at = start + Duration::from_millis(offset);
+            assert!(pool.message(0, Some(signature), Some(offset), at));
+            let late = at + Duration::from_millis(400);
+            assert!(!pool.message(1, Some(signature), Some(offset), late));
+        }
+
+        let scores = pool.scores();
+        assert_eq!(scores[0].mean_lag_ms, 0.0);
+        assert!(scores[1].mean_lag_ms > 200.0);
+        assert!(scores[1].score > scores[0].score);
+        assert_eq!(scores[1].messages, 50);
+    }
+
+    #[test]
+    fn rotates_out_stale_and_flapping_endpoints() {
+        let pool = pool(4);
+        let start = Instant::now();
+        pool.connected(0);
+        pool.connected(1);
+        pool.message(0, None, Some(1), start + Duration::from_secs(15));
+
+        // Endpoint 1 hasn't delivered anything for longer than the cutoff.
+        let now = start + Duration::from_secs(15);
+        assert_eq!(pool.rotate(now), Some((1, 2)));
+        assert_eq!(pool.active(), vec![0, 2]);
+
+        // Endpoint 0 flaps; 1 is benched, so 3 takes over.
+        for _ in 0..3 {
+            pool.connected(0);
+            pool.disconnected(0, now);
+        }
+        pool.message(2, None, None, now);
+        assert_eq!(pool.rotate(now), Some((0, 3)));
+        assert_eq!(pool.rotate(now), None);
+        assert_eq!(pool.active(), vec![2, 3]);
+    }
+
+    #[test]
+    fn backfills_only_after_a_full_outage() {
+        let pool = pool(2);
+        let now = Instant::now();
+        assert_eq!(pool.connected(0), None);
+        pool.message(0, None, Some(42), now);
+        pool.connected(1);
+
+        assert!(pool.disconnected(0, now));
+        assert_eq!(pool.connected(0), None);
+
+        pool.disconnected(0, now);
+        assert!(!pool.disconnected(1, now));
+        assert_eq!(pool.connected(1), Some(42));
+    }
+}
@@ -1,37 +1,837 @@
-use std::time::Duration;
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use crate::{config::Config, events::TokenEvent, state::SniperState};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
 
-#[derive(Debug)]
+use crate::{
+    bundles::BundleStats,
+    config::{Config, DevHistoryConfig, MetadataConfig},
+    dev_history::DevProfile,
+    dev_profiler::FundingProfile,
+    events::TokenEvent,
+    metadata::{link_domain, TokenMetadata},
+    rules::{self, RuleContext, RuleVerdict},
+    state::{ListSource, SniperState},
+};
+
+/// The outcome of filtering a token, with what decided it.
+#[derive(Clone, Debug, PartialEq)]
 pub enum FilterDecision {
-    Allowed,
-    Blacklisted,
-    NotWhitelisted,
-    RateLimited,
+    /// Passed, through the named `allow` rule if one matched.
+    Allowed {
+        rule: Option<String>,
+    },
+    Blacklisted {
+        source: ListSource,
+    },
+    /// Funded by a blacklisted wallet; the developer is blacklisted too.
+    FunderBlacklisted {
+        funder: Pubkey,
+    },
+    NotWhitelisted {
+        whitelist_len: usize,
+    },
+    /// The developer's launches within the last minute, this one included.
+    RateLimited {
+        count: u32,
+        limit: u32,
+    },
     Duplicate,
+    /// Backfilled create older than `max_backfill_age_secs`.
+    Stale {
+        age: Duration,
+        max_age: Duration,
+    },
+    /// The developer wallet is younger than `min_wallet_age_secs`.
+    FreshWallet {
+        age: Duration,
+        min_age: Duration,
+    },
+    /// Funded by a known funder with a rejected label.
+    FunderRejected {
+        funder: Pubkey,
+        label: String,
+    },
+    /// `require_graduation` is set and none of the developer's launches
+    /// graduated.
+    NoGraduation {
+        settled: u32,
+    },
+    /// The developer rugged more than `max_rug_rate_pct` of their settled
+    /// launches.
+    HighRugRate {
+        rate_pct: f64,
+        max_pct: f64,
+        settled: u32,
+    },
+    /// Too many wallets, or too much of the supply, bought in the create's
+    /// slot.
+    Bundled {
+        buyers: u32,
+        supply_pct: f64,
+    },
+    /// The developer's buy in the create transaction was over
+    /// `max_dev_initial_buy_sol` or `max_dev_supply_pct`.
+    DevBuyTooLarge {
+        sol: f64,
+        supply_pct: f64,
+    },
+    /// Rejected by the named filter rule.
+    RuleRejected {
+        rule: String,
+    },
+    /// No social links, or the metadata couldn't be fetched.
+    NoSocials {
+        fetched: bool,
+    },
+    BlockedDomain {
+        domain: String,
+    },
+    /// An earlier token already linked this twitter account.
+    ReusedTwitter {
+        handle: String,
+        first_mint: Pubkey,
+    },
+}
+
+impl FilterDecision {
+    /// Stable name of the decision, for counters and queries.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            FilterDecision::Allowed { .. } => "allowed",
+            FilterDecision::Blacklisted { .. } => "blacklisted",
+            FilterDecision::FunderBlacklisted { .. } => "funder_blacklisted",
+            FilterDecision::NotWhitelisted { .. } => "not_whitelisted",
+            FilterDecision::RateLimited { .. } => "rate_limited",
+            FilterDecision::Duplicate => "duplicate",
+            FilterDecision::Stale { .. } => "stale",
+            FilterDecision::FreshWallet { .. } => "fresh_wallet",
+            FilterDecision::FunderRejected { .. } => "funder_rejected",
+            FilterDecision::NoGraduation { .. } => "no_graduation",
+            FilterDecision::HighRugRate { .. } => "high_rug_rate",
+            FilterDecision::Bundled { .. } => "bundled",
+            FilterDecision::DevBuyTooLarge { .. } => "dev_buy_too_large",
+            FilterDecision::RuleRejected { .. } => "rule_rejected",
+            FilterDecision::NoSocials { .. } => "no_socials",
+            FilterDecision::BlockedDomain { .. } => "blocked_domain",
+            FilterDecision::ReusedTwitter { .. } => "reused_twitter",
+        }
+    }
+}
+
+impl fmt::Display for FilterDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterDecision::Allowed { rule: Some(rule) } => write!(f, "allowed by rule {rule}"),
+            FilterDecision::Allowed { rule: None } => write!(f, "allowed"),
+            FilterDecision::Blacklisted { source } => {
+                write!(f, "developer is blacklisted ({source:?} entry)")
+            }
+            FilterDecision::FunderBlacklisted { funder } => {
+                write!(f, "funded by blacklisted {funder}; developer blacklisted")
+            }
+            FilterDecision::NotWhitelisted { whitelist_len } => {
+                write!(f, "developer not among {whitelist_len} whitelisted")
+            }
+            FilterDecision::RateLimited { count, limit } => {
+                write!(f, "{count} launches in the last minute, limit {limit}")
+            }
+            FilterDecision::Duplicate => write!(f, "already seen"),
+            FilterDecision::Stale { age, max_age } => {
+                write!(f, "backfilled {age:?} late, limit {max_age:?}")
+            }
+            FilterDecision::FreshWallet { age, min_age } => {
+                write!(f, "developer wallet {age:?} old, minimum {min_age:?}")
+            }
+            FilterDecision::FunderRejected { funder, label } => {
+                write!(f, "funded by {label} wallet {funder}")
+            }
+            FilterDecision::NoGraduation { settled } => {
+                write!(f, "none of {settled} settled launches graduated")
+            }
+            FilterDecision::HighRugRate {
+                rate_pct,
+                max_pct,
+                settled,
+            } => write!(
+                f,
+                "rugged {rate_pct:.0}% of {settled} settled launches, limit {max_pct:.0}%"
+            ),
+            FilterDecision::Bundled { buyers, supply_pct } => write!(
+                f,
+                "{buyers} wallets bought {supply_pct:.1}% of the supply in the create slot"
+            ),
+            FilterDecision::DevBuyTooLarge { sol, supply_pct } => write!(
+                f,
+                "developer bought {supply_pct:.1}% of the supply for {sol:.2} SOL at launch"
+            ),
+            FilterDecision::RuleRejected { rule } => write!(f, "rejected by rule {rule}"),
+            FilterDecision::NoSocials { fetched: true } => write!(f, "no social links"),
+            FilterDecision::NoSocials { fetched: false } => {
+                write!(f, "metadata not fetched in time")
+            }
+            FilterDecision::BlockedDomain { domain } => write!(f, "links to blocked {domain}"),
+            FilterDecision::ReusedTwitter { handle, first_mint } => {
+                write!(f, "twitter {handle} already used by {first_mint}")
+            }
+        }
+    }
+}
+
+/// A rejected token, kept for "why didn't we buy X?".
+#[derive(Clone, Debug, Serialize)]
+pub struct Rejection {
+    /// Unix milliseconds.
+    pub at_ms: u64,
+    pub mint: String,
+    pub developer: String,
+    pub name: String,
+    pub symbol: String,
+    pub reason: &'static str,
+    pub detail: String,
+}
+
+/// Rejection counts by reason and the latest rejections, shared with
+/// readers.
+#[derive(Clone, Debug)]
+pub struct RejectionLog {
+    counts: Arc<DashMap<&'static str, u64>>,
+    recent: Arc<Mutex<VecDeque<Rejection>>>,
+    capacity: usize,
+}
+
+impl RejectionLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            counts: Arc::new(DashMap::new()),
+            recent: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Counts a rejection and keeps it, unless it is a duplicate.
+    pub fn record(&self, event: &TokenEvent, decision: &FilterDecision) {
+        if let FilterDecision::Allowed { .. } = decision {
+            return;
+        }
+        *self.counts.entry(decision.reason()).or_default() += 1;
+        if *decision == FilterDecision::Duplicate || self.capacity == 0 {
+            return;
+        }
+        let rejection = Rejection {
+            at_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            mint: event.mint.to_string(),
+            developer: event.developer.to_string(),
+            name: event.name.clone(),
+            symbol: event.symbol.clone(),
+            reason: decision.reason(),
+            detail: decision.to_string(),
+        };
+        let mut recent = self.recent.lock();
+        if recent.len() >= self.capacity {
+            recent.pop_front();
+        }
+        recent.push_back(rejection);
+    }
+
+    /// Rejections per reason, most frequent first.
+    pub fn counts(&self) -> Vec<(&'static str, u64)> {
+        let mut counts: Vec<_> = self
+            .counts
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    /// The latest `limit` rejections, newest first.
+    pub fn recent(&self, limit: usize) -> Vec<Rejection> {
+        self.recent
+            .lock()
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// The latest rejection of `mint`, if it is still buffered.
+    pub fn find(&self, mint: &Pubkey) -> Option<Rejection> {
+        let mint = mint.to_string();
+        self.recent
+            .lock()
+            .iter()
+            .rev()
+            .find(|rejection| rejection.mint == mint)
+            .cloned()
+    }
+
+    /// One-line summary for periodic logging.
+    pub fn summary(&self) -> String {
+        self.counts()
+            .iter()
+            .map(|(reason, count)| format!("{reason}={count}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
-pub fn apply_filters(event: &TokenEvent, config: &Config, state: &SniperState) -> FilterDecision {
+/// What the filters know beyond the event, gathered asynchronously first.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FilterInputs<'a> {
+    /// The developer's funding profile, when profiling is on and it finished
+    /// in time.
+    pub funding: Option<&'a FundingProfile>,
+    /// Buys in the create's slot, when bundles are tracked and the slot is
+    /// known.
+    pub bundle: Option<BundleStats>,
+}
+
+/// The developer's launches in the last minute against the limit, counted
+/// by `check_developer`.
+#[derive(Clone, Copy, Debug)]
+pub struct RateCount {
+    count: u32,
+    limit: u32,
+}
+
+impl RateCount {
+    pub fn exceeded(&self) -> bool {
+        self.count > self.limit
+    }
+}
+
+/// The checks that need nothing fetched: duplicates, staleness and the
+/// developer lists. Run first so nothing is fetched for launches they
+/// reject. Every launch that passes counts toward its developer's rate,
+/// including ones a later filter rejects.
+pub fn check_developer(
+    event: &TokenEvent,
+    config: &Config,
+    state: &SniperState,
+) -> Result<RateCount, FilterDecision> {
     if state.seen_mints.contains(&event.mint) {
-        return FilterDecision::Duplicate;
+        return Err(FilterDecision::Duplicate);
+    }
+
+    let max_age = config.max_backfill_age();
+    if let Some(age) = event.backfill_age.filter(|age| *age > max_age) {
+        return Err(FilterDecision::Stale { age, max_age });
     }
 
-    if state.filters.is_blacklisted(&event.developer) {
-        return FilterDecision::Blacklisted;
+    if let Some(source) = state.filters.blacklist_source(&event.developer) {
+        return Err(FilterDecision::Blacklisted { source });
     }
 
     if !state.filters.is_whitelisted(&event.developer) {
-        return FilterDecision::NotWhitelisted;
+        return Err(FilterDecision::NotWhitelisted {
+            whitelist_len: state.filters.whitelist.len(),
+        });
+    }
+
+    let limit = config.dev_filters.dev_max_tokens_per_min.unwrap_or(10);
+    let count = state
+        .rate_limiter
+        .record(&event.developer, Duration::from_secs(60));
+    Ok(RateCount { count, limit })
+}
+
+/// The rest of the filters, for a launch `check_developer` passed.
+pub fn apply_filters(
+    event: &TokenEvent,
+    config: &Config,
+    state: &SniperState,
+    rate: RateCount,
+    inputs: FilterInputs,
+) -> FilterDecision {
+    let FilterInputs { funding, bundle } = inputs;
+    let blacklist_funded = config
+        .dev_profiler
+        .as_ref()
+        .is_some_and(|profiler| profiler.blacklist_funded());
+    if let Some(funder) = funding.filter(|_| blacklist_funded).and_then(|funding| {
+        funding
+            .funders
+            .iter()
+            .find(|funder| state.filters.is_blacklisted(funder))
+    }) {
+        // The caller blacklists the developer, off the filter path.
+        return FilterDecision::FunderBlacklisted { funder: *funder };
+    }
+
+    let dev = state
+        .dev_history
+        .as_ref()
+        .map(|history| history.profile(&event.developer));
+    if let (Some(profile), Some(history)) = (dev, &config.dev_history) {
+        if let Some(decision) = apply_history_filters(profile, history) {
+            return decision;
+        }
+    }
+
+    let wallet_age = funding.and_then(|funding| funding.wallet_age(SystemTime::now()));
+    if let (Some(profiler), Some(state_profiler)) = (&config.dev_profiler, &state.dev_profiler) {
+        let min_age = profiler.min_wallet_age_secs.map(Duration::from_secs);
+        if let (Some(age), Some(min_age)) = (wallet_age, min_age) {
+            let old_enough = funding.is_some_and(|funding| !funding.complete);
+            if age < min_age && !old_enough {
+                return FilterDecision::FreshWallet { age, min_age };
+            }
+        }
+        for funder in funding.into_iter().flat_map(|funding| &funding.funders) {
+            let Some(label) = state_profiler.label(funder) else {
+                continue;
+            };
+            if profiler
+                .reject_funder_labels
+                .iter()
+                .any(|reject| reject == label)
+            {
+                return FilterDecision::FunderRejected {
+                    funder: *funder,
+                    label: label.to_string(),
+                };
+            }
+        }
+    }
+
+    if let (Some(stats), Some(bundles)) = (bundle, &config.bundles) {
+        let too_many = bundles
+            .max_same_slot_buyers
+            .is_some_and(|max| stats.buyers > max);
+        let too_much = bundles
+            .max_same_slot_supply_pct
+            .is_some_and(|max| stats.supply_pct > max);
+        if too_many || too_much {
+            return FilterDecision::Bundled {
+                buyers: stats.buyers,
+                supply_pct: stats.supply_pct,
+            };
+        }
+    }
+
+    if let Some(buy) = event.initial_buy {
+        let filters = &config.dev_filters;
+        let (sol, supply_pct) = (buy.sol(), buy.supply_pct());
+        let too_much_sol = filters.max_dev_initial_buy_sol.is_some_and(|max| sol > max);
+        let too_much_supply = filters
+            .max_dev_supply_pct
+            .is_some_and(|max| supply_pct > max);
+        if too_much_sol || too_much_supply {
+            return FilterDecision::DevBuyTooLarge { sol, supply_pct };
+        }
+    }
+
+    let context = RuleContext {
+        dev,
+        wallet_age,
+        funder: funding.and_then(FundingProfile::funder),
+        bundle,
+        ..state.slots.rule_context()
+    };
+    let verdict = rules::evaluate(&state.filters.rules, event, context);
+    let rule = match verdict {
+        Some(RuleVerdict::Rejected(rule)) => return FilterDecision::RuleRejected { rule },
+        Some(RuleVerdict::Allowed(rule)) => Some(rule),
+        None => None,
+    };
+
+    // An `allow` rule doesn't lift the rate limit; it's checked last so a
+    // rejection by any other filter is reported first.
+    if rate.exceeded() {
+        return FilterDecision::RateLimited {
+            count: rate.count,
+            limit: rate.limit,
+        };
+    }
+
+    FilterDecision::Allowed { rule }
+}
+
+fn apply_history_filters(profile: DevProfile, config: &DevHistoryConfig) -> Option<FilterDecision> {
+    if config.require_graduation && profile.graduated == 0 {
+        return Some(FilterDecision::NoGraduation {
+            settled: profile.settled(),
+        });
+    }
+    let max_pct = config.max_rug_rate_pct?;
+    let rate_pct = profile.rug_rate_pct()?;
+    let settled = profile.settled();
+    (settled >= config.min_settled_launches() && rate_pct > max_pct).then_some(
+        FilterDecision::HighRugRate {
+            rate_pct,
+            max_pct,
+            settled,
+        },
+    )
+}
+
+/// Checks a token's off-chain metadata, `None` when it couldn't be fetched.
+/// Returns the rejection, if any.
+pub fn apply_metadata_filters(
+    event: &TokenEvent,
+    metadata: Option<&TokenMetadata>,
+    config: &MetadataConfig,
+    state: &SniperState,
+) -> Option<FilterDecision> {
+    let Some(metadata) = metadata else {
+        return config
+            .require_socials
+            .then_some(FilterDecision::NoSocials { fetched: false });
+    };
+    if config.require_socials && !metadata.has_socials() {
+        return Some(FilterDecision::NoSocials { fetched: true });
+    }
+
+    for domain in metadata.links().filter_map(link_domain) {
+        let blocked = config.blocked_domains.iter().any(|blocked| {
+            let blocked = blocked.to_lowercase();
+            domain == blocked || domain.ends_with(&format!(".{blocked}"))
+        });
+        if blocked {
+            return Some(FilterDecision::BlockedDomain { domain });
+        }
+    }
+
+    if let Some(handle) = metadata.twitter_handle() {
+        let first = *state
+            .filters
+            .twitter_handles
+            .entry(handle.clone())
+            .or_insert(event.mint);
+        if config.reject_reused_twitter && first != event.mint {
+            return Some(FilterDecision::ReusedTwitter {
+                handle,
+                first_mint: first,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    use super::*;
+    use crate::events::{pump_fun, InitialBuy};
+
+    fn event() -> TokenEvent {
+        TokenEvent {
+            name: "Frog".to_string(),
+            symbol: "FROG".to_string(),
+            ..TokenEvent::for_test(Pubkey::new_unique(), Pubkey::new_unique())
+        }
+    }
+
+    /// Both filter stages, as `handle_event` runs them.
+    fn filter(
+        event: &TokenEvent,
+        config: &Config,
+        state: &SniperState,
+        inputs: FilterInputs,
+    ) -> FilterDecision {
+        match check_developer(event, config, state) {
+            Ok(rate) => apply_filters(event, config, state, rate, inputs),
+            Err(rejection) => rejection,
+        }
+    }
+
+    /// A config with `sections` (which must include `[dev_filters]`) and the
+    /// state built from it.
+    fn setup(sections: &str) -> (Config, SniperState) {
+        let config: Config = toml::from_str(&format!(
+            r#"
+            keypair_path = "unused.json"
+            purchase_strategy = {{ fixed_sol = 0.1 }}
+
+            [endpoints]
+            rpc_http_url = "http://127.0.0.1:8899"
+
+            [fee_config]
+
+            {sections}
+            "#
+        ))
+        .unwrap();
+        let rpc = Arc::new(RpcClient::new(config.endpoints.rpc_http_url.clone()));
+        let state = SniperState::new(&config, rpc).unwrap();
+        (config, state)
     }
 
-    let max_per_minute = config.dev_filters.dev_max_tokens_per_min.unwrap_or(10);
-    let allowed =
+    #[test]
+    fn rate_limit_counts_every_listed_launch() {
+        let (config, state) = setup(
+            r#"
+            [dev_filters]
+            dev_max_tokens_per_min = 2
+
+            [[filter_rules]]
+            name = "no_dogs"
+            action = "reject"
+            when = 'symbol == "DOG"'
+
+            [[filter_rules]]
+            name = "frogs"
+            action = "allow"
+            when = 'symbol == "FROG"'
+            "#,
+        );
+        let developer = Pubkey::new_unique();
+        let launch = |symbol: &str| TokenEvent {
+            symbol: symbol.to_string(),
+            ..TokenEvent::for_test(Pubkey::new_unique(), developer)
+        };
+
+        // A rule rejection is reported first but still counts.
+        let decide = |symbol| filter(&launch(symbol), &config, &state, Default::default());
+        assert!(matches!(decide("DOG"), FilterDecision::RuleRejected { .. }));
+        assert!(matches!(decide("FROG"), FilterDecision::Allowed { .. }));
+        assert!(matches!(decide("DOG"), FilterDecision::RuleRejected { .. }));
+        assert_eq!(
+            decide("FROG"),
+            FilterDecision::RateLimited { count: 4, limit: 2 }
+        );
+    }
+
+    #[test]
+    fn filters_on_metadata() {
+        let (_, state) = setup("[dev_filters]");
+        let config = MetadataConfig {
+            require_socials: true,
+            blocked_domains: vec!["Rug.Site".to_string()],
+            reject_reused_twitter: true,
+            ..MetadataConfig::default()
+        };
+        let linking = |twitter: &str, website: &str| TokenMetadata {
+            twitter: Some(twitter.to_string()),
+            website: Some(website.to_string()),
+            ..TokenMetadata::default()
+        };
+        let check = |event: &TokenEvent, metadata: Option<&TokenMetadata>| {
+            apply_metadata_filters(event, metadata, &config, &state)
+        };
+
+        assert_eq!(
+            check(&event(), None),
+            Some(FilterDecision::NoSocials { fetched: false })
+        );
+        assert_eq!(
+            check(&event(), Some(&TokenMetadata::default())),
+            Some(FilterDecision::NoSocials { fetched: true })
+        );
+        assert_eq!(
+            check(&event(), Some(&linking("@frog", "https://app.rug.site/x"))),
+            Some(FilterDecision::BlockedDomain {
+                domain: "app.rug.site".to_string()
+            })
+        );
+        // Only the domain itself and its subdomains are blocked.
+        let first = event();
+        let frog = linking("https://x.com/Frog/status/1", "https://notrug.site");
+        assert_eq!(check(&first, Some(&frog)), None);
+        // The first mint linking a handle keeps it; a later one is rejected.
+        assert_eq!(check(&first, Some(&frog)), None);
+        assert_eq!(
+            check(&event(), Some(&linking("@frog", "frog.site"))),
+            Some(FilterDecision::ReusedTwitter {
+                handle: "frog".to_string(),
+                first_mint: first.mint,
+            })
+        );
+    }
+
+    #[test]
+    fn filters_on_dev_history() {
+        let config = DevHistoryConfig {
+            max_rug_rate_pct: Some(50.0),
+            min_settled_launches: Some(3),
+            require_graduation: true,
+            ..DevHistoryConfig::default()
+        };
+        let profile = |graduated, rugged, abandoned| DevProfile {
+            launches: graduated + rugged + abandoned + 1,
+            graduated,
+            rugged,
+            abandoned,
+        };
+
+        assert_eq!(
+            apply_history_filters(profile(0, 0, 2), &config),
+            Some(FilterDecision::NoGraduation { settled: 2 })
+        );
+        assert_eq!(
+            apply_history_filters(profile(1, 2, 0), &config),
+            Some(FilterDecision::HighRugRate {
+                rate_pct: 200.0 / 3.0,
+                max_pct: 50.0,
+                settled: 3,
+            })
+        );
+        // Too few settled launches to judge the rug rate, or a low one.
+        assert_eq!(apply_history_filters(profile(1, 1, 0), &config), None);
+        assert_eq!(apply_history_filters(profile(2, 1, 1), &config), None);
+        let lenient = DevHistoryConfig {
+            require_graduation: false,
+            ..config
+        };
+        assert_eq!(apply_history_filters(profile(0, 0, 0), &lenient), None);
+    }
+
+    #[test]
+    fn rejects_bundled_launches() {
+        let (config, state) = setup(
+            r#"
+            [dev_filters]
+
+            [bundles]
+            max_same_slot_buyers = 3
+            max_same_slot_supply_pct = 20.0
+            "#,
+        );
+        let decide = |buyers, supply_pct| {
+            let inputs = FilterInputs {
+                funding: None,
+                bundle: Some(BundleStats { buyers, supply_pct }),
+            };
+            filter(&event(), &config, &state, inputs)
+        };
+
+        assert_eq!(
+            decide(4, 5.0),
+            FilterDecision::Bundled {
+                buyers: 4,
+                supply_pct: 5.0
+            }
+        );
+        assert_eq!(
+            decide(2, 25.0),
+            FilterDecision::Bundled {
+                buyers: 2,
+                supply_pct: 25.0
+            }
+        );
+        assert_eq!(decide(3, 20.0), FilterDecision::Allowed { rule: None });
+    }
+
+    #[test]
+    fn rejects_large_dev_buys() {
+        let (config, state) = setup(
+            r#"
+            [dev_filters]
+            max_dev_initial_buy_sol = 2.0
+            max_dev_supply_pct = 10.0
+            "#,
+        );
+        let decide = |sol: u64, supply_pct: u64| {
+            let event = TokenEvent {
+                initial_buy: Some(InitialBuy {
+                    lamports: sol * 1_000_000_000,
+                    tokens: pump_fun::TOKEN_TOTAL_SUPPLY / 100 * supply_pct,
+                }),
+                ..event()
+            };
+            filter(&event, &config, &state, FilterInputs::default())
+        };
+
+        assert_eq!(
+            decide(3, 1),
+            FilterDecision::DevBuyTooLarge {
+                sol: 3.0,
+                supply_pct: 1.0
+            }
+        );
+        assert_eq!(
+            decide(1, 15),
+            FilterDecision::DevBuyTooLarge {
+                sol: 1.0,
+                supply_pct: 15.0
+            }
+        );
+        assert_eq!(decide(1, 5), FilterDecision::Allowed { rule: None });
+    }
+
+    #[tokio::test]
+    async fn blacklisted_funder_blacklists_developer() {
+        let funder = Pubkey::new_unique();
+        let (config, state) = setup(&format!(
+            r#"
+            [dev_filters]
+            dev_blacklist = ["{funder}"]
+
+            [dev_profiler]
+            "#
+        ));
+        let event = event();
+        let funding = FundingProfile {
+            first_activity: None,
+            complete: true,
+            funders: vec![Pubkey::new_unique(), funder],
+        };
+        let inputs = FilterInputs {
+            funding: Some(&funding),
+            bundle: None,
+        };
+
+        let decision = filter(&event, &config, &state, inputs);
+        assert_eq!(decision, FilterDecision::FunderBlacklisted { funder });
         state
-            .rate_limiter
-            .is_allowed(&event.developer, max_per_minute, Duration::from_secs(60));
-    if !allowed {
-        return FilterDecision::RateLimited;
+            .filters
+            .spread_blacklist(event.developer)
+            .await
+            .unwrap();
+        assert!(state.filters.is_blacklisted(&event.developer));
+        assert!(matches!(
+            filter(&event, &config, &state, FilterInputs::default()),
+            FilterDecision::Blacklisted { .. }
+        ));
     }
 
-    FilterDecision::Allowed
+    #[test]
+    fn explains_and_buffers_rejections() {
+        let log = RejectionLog::new(2);
+        let limited = event();
+        log.record(
+            &limited,
+            &FilterDecision::RateLimited { count: 4, limit: 3 },
+        );
+        log.record(&event(), &FilterDecision::Allowed { rule: None });
+        log.record(&event(), &FilterDecision::Duplicate);
+        for _ in 0..2 {
+            let rule = FilterDecision::RuleRejected {
+                rule: "cats_only".to_string(),
+            };
+            log.record(&event(), &rule);
+        }
+
+        assert_eq!(
+            log.counts(),
+            [("rule_rejected", 2), ("duplicate", 1), ("rate_limited", 1)]
+        );
+        // The rate-limited create was evicted by the two newer rejections.
+        assert!(log.find(&limited.mint).is_none());
+        let recent = log.recent(10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].detail, "rejected by rule cats_only");
+
+        let log = RejectionLog::new(4);
+        log.record(
+            &limited,
+            &FilterDecision::RateLimited { count: 4, limit: 3 },
+        );
+        let rejection = log.find(&limited.mint).unwrap();
+        assert_eq!(rejection.reason, "rate_limited");
+        assert_eq!(rejection.detail, "4 launches in the last minute, limit 3");
+    }
 }
@@ -0,0 +1,180 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+
+/// Buckets per doubling of the latency, so a reported percentile is within
+/// about 9% of the true value.
+const BUCKETS_PER_OCTAVE: f64 = 8.0;
+/// Enough octaves of microseconds to cover ~140 minutes.
+const BUCKETS: usize = 8 * 33 + 1;
+
+/// A step of the hot path between socket read and submission.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    /// Receive to the start of processing: dedup and channel backlog.
+    Queue,
+    /// Waiting for trades in the create's slot.
+    Bundle,
+    /// Waiting on the developer's funding profile.
+    Profile,
+    Filter,
+    /// Waiting on the token's off-chain metadata.
+    Metadata,
+    /// Instruction building.
+    Build,
+    /// Message compilation and signing.
+    Sign,
+    SendRpc,
+    SendJito,
+    SendNozomi,
+    /// `dispatch_transaction` until its first success or last failure.
+    Dispatch,
+    /// Receive until `dispatch_transaction` returned.
+    Total,
+}
+
+/// Log-bucketed latency histogram, in microseconds.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    max_us: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            count: 0,
+            max_us: 0,
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, elapsed: Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        self.counts[bucket(us)] += 1;
+        self.count += 1;
+        self.max_us = self.max_us.max(us);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile (0.0 to 1.0).
+    pub fn percentile(&self, q: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((self.count as f64 * q).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Duration::from_micros(upper_bound(index).min(self.max_us));
+            }
+        }
+        Duration::from_micros(self.max_us)
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.max_us)
+    }
+}
+
+fn bucket(us: u64) -> usize {
+    if us == 0 {
+        return 0;
+    }
+    let index = ((us as f64).log2() * BUCKETS_PER_OCTAVE).floor() as usize + 1;
+    index.min(BUCKETS - 1)
+}
+
+fn upper_bound(index: usize) -> u64 {
+    if index == 0 {
+        return 0;
+    }
+    2f64.powf(index as f64 / BUCKETS_PER_OCTAVE).ceil() as u64
+}
+
+/// Percentiles of one stage, for logs and metrics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StageLatency {
+    pub count: u64,
+    pub p50: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// Per-stage histograms shared by the hot path and readers.
+#[derive(Clone, Debug, Default)]
+pub struct LatencyStats {
+    inner: Arc<Mutex<HashMap<Stage, Histogram>>>,
+}
+
+impl LatencyStats {
+    pub fn record(&self, stage: Stage, elapsed: Duration) {
+        self.inner.lock().entry(stage).or_default().record(elapsed);
+    }
+
+    pub fn snapshot(&self) -> Vec<(Stage, StageLatency)> {
+        let mut stages: Vec<_> = self
+            .inner
+            .lock()
+            .iter()
+            .map(|(stage, histogram)| {
+                let latency = StageLatency {
+                    count: histogram.count(),
+                    p50: histogram.percentile(0.5),
+                    p99: histogram.percentile(0.99),
+                    max: histogram.max(),
+                };
+                (*stage, latency)
+            })
+            .collect();
+        stages.sort_by_key(|(stage, _)| *stage);
+        stages
+    }
+
+    /// One-line summary for periodic logging.
+    pub fn summary(&self) -> String {
+        self.snapshot()
+            .iter()
+            .map(|(stage, latency)| {
+                format!(
+                    "{stage:?} p50 {:?} p99 {:?} (n={})",
+                    latency.p50, latency.p99, latency.count
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_percentiles_within_bucket_error() {
+        let stats = LatencyStats::default();
+        for us in 1..=1_000 {
+            stats.record(Stage::Build, Duration::from_micros(us));
+        }
+        stats.record(Stage::Filter, Duration::ZERO);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot[0].0, Stage::Filter);
+        assert_eq!(snapshot[0].1.p99, Duration::ZERO);
+
+        let build = &snapshot[1].1;
+        assert_eq!(build.count, 1_000);
+        assert_eq!(build.max, Duration::from_micros(1_000));
+        let p50 = build.p50.as_micros() as f64;
+        let p99 = build.p99.as_micros() as f64;
+        assert!((500.0..=500.0 * 1.1).contains(&p50), "p50 {p50}");
+        assert!((990.0..=1_000.0).contains(&p99), "p99 {p99}");
+    }
+}
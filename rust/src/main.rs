@@ -1,30 +1,64 @@
+mod admin;
+mod bundles;
 mod config;
+mod dev_history;
+mod dev_profiler;
 mod events;
 mod filters;
+mod latency;
+mod metadata;
+mod rules;
 mod state;
 mod transactions;
 
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
-use config::Config;
-use events::{EventSupervisor, TokenEvent};
-use filters::{apply_filters, FilterDecision};
+use anyhow::{anyhow, Context, Result};
+use config::{Config, MetadataConfig};
+use events::{
+    launchlab::WSOL_MINT, pump_fun, pump_swap, CopyTradeEvent, EventSupervisor, LeaderTrade,
+    Platform, SourceEvent, TokenEvent,
+};
+use filters::{
+    apply_filters, apply_metadata_filters, check_developer, FilterDecision, FilterInputs,
+};
+use latency::Stage;
+use metadata::{MetadataMode, TokenMetadata};
 use reqwest::Client;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::Signer;
-use transactions::{dispatch_transaction, TransactionBuilder};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use tokio::{sync::Semaphore, task::JoinHandle};
+use transactions::{
+    builder::{CurveTrade, Market, SwapTrade},
+    dispatch_transaction, TransactionBuilder,
+};
+
+const RACE_STATS_INTERVAL: Duration = Duration::from_secs(60);
+const MARKET_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+/// Markets with no trade for this long are dropped from `SniperState`.
+const MARKET_IDLE_SECS: i64 = 30 * 60;
+/// Early buys of a mint are kept this long for bundle checks.
+const BUNDLE_WINDOW: Duration = Duration::from_secs(120);
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::init();
     let config_path =
         std::env::var("SNIPER_CONFIG").unwrap_or_else(|_| "rust/config.example.toml".to_string());
     let config = Arc::new(Config::from_file(config_path)?);
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(config.log_level()))
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return run_command(&config, &args).await;
+    }
 
     let payer = Arc::new(config.load_keypair()?);
     let rpc_client = Arc::new(RpcClient::new(config.endpoints.rpc_http_url.clone()));
-    let state = state::SniperState::new(&config, rpc_client.clone())?;
+    let state = Arc::new(state::SniperState::new(&config, rpc_client.clone())?);
 
     let blockhash_interval = config.blockhash_refresh_interval();
     let _blockhash_task = state
@@ -41,63 +75,383 @@ async fn main() -> Result<()> {
         }
     });
 
-    let event_supervisor = EventSupervisor::new(config.clone());
+    let markets = state.markets.clone();
+    let dev_history = state.dev_history.clone();
+    let bundles = state.bundles.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(MARKET_PRUNE_INTERVAL).await;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            markets.prune(now, MARKET_IDLE_SECS);
+            if let Some(bundles) = &bundles {
+                bundles.prune(BUNDLE_WINDOW);
+            }
+            log::debug!("Tracking {} live markets", markets.len());
+            if let Some(history) = &dev_history {
+                let abandoned = history.sweep(std::time::SystemTime::now());
+                log::debug!("Marked {abandoned} launches abandoned");
+            }
+        }
+    });
+
+    let lists = &config.dev_filters;
+    if lists.dev_whitelist_file.is_some() || lists.dev_blacklist_file.is_some() {
+        let _list_reloader = state.filters.spawn_reloader(lists.list_reload_interval());
+    }
+    if let Some(listen) = &config.admin_listen {
+        let _admin = admin::spawn(listen, state.filters.clone(), state.rejections.clone()).await?;
+    }
+
+    let event_supervisor = EventSupervisor::new(config.clone())?;
     let mut receiver = event_supervisor.start();
-    let builder =
-        TransactionBuilder::new(config.clone(), payer.clone(), state.blockhash_cache.clone())?;
+    let race_stats = event_supervisor.race_stats().clone();
+    let supervisor_health = event_supervisor.clone();
+    let latency = state.latency.clone();
+    let pipeline = state.pipeline.clone();
+    let rejections = state.rejections.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(RACE_STATS_INTERVAL).await;
+            let summary = race_stats.summary();
+            if !summary.is_empty() {
+                log::info!("Source race: {summary}");
+            }
+            let queue = supervisor_health.queue_stats();
+            if queue.overflow() > 0 || queue.stale() > 0 {
+                log::warn!(
+                    "Event queue shed {} events on overflow and {} stale creates (depth {})",
+                    queue.overflow(),
+                    queue.stale(),
+                    queue.depth()
+                );
+            }
+            log::info!(
+                "Pipeline: {} events processed, {} failed",
+                pipeline.processed(),
+                pipeline.failed()
+            );
+            let summary = latency.summary();
+            if !summary.is_empty() {
+                log::info!("Latency: {summary}");
+            }
+            let summary = rejections.summary();
+            if !summary.is_empty() {
+                log::info!("Rejections: {summary}");
+            }
+            for (name, health) in supervisor_health.health() {
+                log::info!(
+                    "Source {name}: connected={} events={} restarts={} last_error={:?}",
+                    health.connected,
+                    health.events,
+                    health.restarts,
+                    health.last_error
+                );
+            }
+            for (name, score) in supervisor_health.endpoint_scores() {
+                log::info!(
+                    "Endpoint {} ({name}): active={} connected={} score={:.0} lag={:.1}ms disconnects/h={} stale={} messages={}",
+                    score.endpoint,
+                    score.active,
+                    score.connected,
+                    score.score,
+                    score.mean_lag_ms,
+                    score.disconnects,
+                    score.stale,
+                    score.messages
+                );
+            }
+        }
+    });
+    let builder = Arc::new(TransactionBuilder::new(
+        config.clone(),
+        payer.clone(),
+        state.blockhash_cache.clone(),
+        state.latency.clone(),
+    )?);
     let http_client = Client::new();
+    let permits = Arc::new(Semaphore::new(config.max_concurrent_events()));
 
     log::info!("Sniper bot initialized; waiting for events");
 
     while let Some(event) = receiver.recv().await {
-        handle_event(
-            &config,
-            &state,
-            &builder,
-            &http_client,
-            rpc_client.clone(),
-            &event,
-        )
-        .await?;
+        if let Some(slot) = event.slot() {
+            state.slots.observe(slot);
+        }
+        match event {
+            SourceEvent::Token(event) => {
+                if let Some(history) = &state.dev_history {
+                    history.record_launch(&event);
+                }
+                let Some(guard) = state.in_flight.claim(event.mint) else {
+                    log::debug!("{} is already being processed", event.mint);
+                    continue;
+                };
+                let permits = permits.clone();
+                let config = config.clone();
+                let state = state.clone();
+                let builder = builder.clone();
+                let http_client = http_client.clone();
+                let rpc_client = rpc_client.clone();
+                tokio::spawn(async move {
+                    let result = handle_event(
+                        &config,
+                        &state,
+                        &builder,
+                        &http_client,
+                        rpc_client,
+                        &permits,
+                        &event,
+                    )
+                    .await;
+                    state.pipeline.record(result.is_ok());
+                    if let Err(err) = result {
+                        log::error!("Failed to process token {}: {err:#}", event.mint);
+                    }
+                    drop(guard);
+                });
+            }
+            SourceEvent::Trade(update) => {
+                let mint = update.trade.mint;
+                state.markets.apply(&update.trade, update.slot);
+                if let Some(history) = &state.dev_history {
+                    history.observe_trade(&update.trade);
+                }
+                if let Some(bundles) = &state.bundles {
+                    bundles.observe(&update.trade, update.slot);
+                }
+                if let Some(market) = state.markets.get(&mint) {
+                    log::trace!(
+                        "{:?} trade on {mint}: price {:.10} SOL, {} buys / {} sells",
+                        update.source,
+                        market.price_sol(),
+                        market.buys,
+                        market.sells
+                    );
+                }
+            }
+            SourceEvent::Migration(migration) => {
+                if let Some(history) = &state.dev_history {
+                    history.graduated(&migration.mint);
+                }
+                if state.pools.insert(migration.pool, migration.mint) {
+                    log::info!(
+                        "{} graduated at slot {:?} ({:?} via {:?}); PumpSwap pool {}",
+                        migration.mint,
+                        migration.slot,
+                        migration.kind,
+                        migration.source,
+                        migration.pool
+                    );
+                }
+            }
+            SourceEvent::CopyTrade(event) => {
+                let permits = permits.clone();
+                let config = config.clone();
+                let state = state.clone();
+                let builder = builder.clone();
+                let http_client = http_client.clone();
+                let rpc_client = rpc_client.clone();
+                let owner = payer.pubkey();
+                tokio::spawn(async move {
+                    let Ok(_permit) = permits.acquire_owned().await else {
+                        return;
+                    };
+                    let result = handle_copy_trade(
+                        &config,
+                        &state,
+                        &builder,
+                        &http_client,
+                        rpc_client,
+                        &owner,
+                        &event,
+                    )
+                    .await;
+                    state.pipeline.record(result.is_ok());
+                    if let Err(err) = result {
+                        log::warn!("Failed to mirror {} trade: {err}", event.leader);
+                    }
+                });
+            }
+        }
     }
 
     Ok(())
 }
 
+/// One-off commands run instead of the bot:
+/// `dev-history export <file>`, `dev-history import <file>`, and
+/// `admin <command>` against a running bot.
+async fn run_command(config: &Config, args: &[String]) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let (import, file) = match args.as_slice() {
+        ["dev-history", "export", file] => (false, *file),
+        ["dev-history", "import", file] => (true, *file),
+        ["admin", command @ ..] if !command.is_empty() => {
+            let Some(address) = &config.admin_listen else {
+                anyhow::bail!("admin commands need admin_listen to be set");
+            };
+            println!("{}", admin::send(address, &command.join(" ")).await?);
+            return Ok(());
+        }
+        _ => anyhow::bail!(
+            "Usage: pumpfun-sniper [dev-history export|import <file>] [admin {}]",
+            admin::USAGE
+        ),
+    };
+    let Some(history_config) = &config.dev_history else {
+        anyhow::bail!("dev-history commands need a [dev_history] section");
+    };
+    let Some(path) = &history_config.path else {
+        anyhow::bail!("dev-history commands need dev_history.path to be set");
+    };
+    if import {
+        let count = dev_history::import_csv(file, path)?;
+        log::info!("Imported {count} launches from {file} into {path}");
+    } else {
+        let count = dev_history::export_csv(path, file)?;
+        log::info!("Exported {count} launches to {file}");
+    }
+    Ok(())
+}
+
 async fn handle_event(
     config: &Config,
     state: &state::SniperState,
     builder: &TransactionBuilder,
     http_client: &Client,
     rpc_client: Arc<RpcClient>,
+    permits: &Semaphore,
     event: &TokenEvent,
 ) -> Result<()> {
-    match apply_filters(event, config, state) {
-        FilterDecision::Allowed => {
+    let started = Instant::now();
+    let latency = &state.latency;
+    latency.record(
+        Stage::Queue,
+        started.saturating_duration_since(event.received_instant),
+    );
+    let checked = Instant::now();
+    let rate = match check_developer(event, config, state) {
+        Ok(rate) => rate,
+        Err(rejection) => {
+            latency.record(Stage::Filter, checked.elapsed());
+            reject(state, event, &rejection);
+            return Ok(());
+        }
+    };
+    let checked = checked.elapsed();
+
+    // Only launches the developer checks passed are profiled, during the
+    // bundle wait; a rate-limited one is rejected either way.
+    let profile = async {
+        let profiler = state.dev_profiler.as_ref().filter(|_| !rate.exceeded())?;
+        let profiled = Instant::now();
+        let funding = profiler
+            .profile(&event.developer)
+            .await
+            .inspect_err(|err| log::info!("No funding profile for {}: {err}", event.developer))
+            .ok();
+        latency.record(Stage::Profile, profiled.elapsed());
+        funding
+    };
+    let bundle_wait = async {
+        let (Some(tracker), Some(bundles), Some(slot)) =
+            (&state.bundles, &config.bundles, event.slot)
+        else {
+            return None;
+        };
+        let waited = Instant::now();
+        tokio::time::sleep_until((event.received_instant + bundles.wait()).into()).await;
+        latency.record(Stage::Bundle, waited.elapsed());
+        Some(tracker.stats(&event.mint, slot))
+    };
+    let (funding, bundle) = tokio::join!(profile, bundle_wait);
+    // Taken after the waits so sleeping creates don't hold up the rest, and
+    // in the task rather than the receive loop so trades and migrations keep
+    // flowing when saturated.
+    let _permit = permits.acquire().await?;
+
+    let filtered = Instant::now();
+    let inputs = FilterInputs {
+        funding: funding.as_deref(),
+        bundle,
+    };
+    let decision = apply_filters(event, config, state, rate, inputs);
+    latency.record(Stage::Filter, checked + filtered.elapsed());
+    match decision {
+        FilterDecision::Allowed { rule } => {
             log::info!(
-                "Event passed filters from {:?}: {}",
+                "Event passed filters from {:?}: {} (slot {:?}, rule {:?})",
                 event.source,
-                event.mint
+                event.mint,
+                event.slot,
+                rule
+            );
+            log::debug!(
+                "{:?} token {} ({} / {}, {}) tx {:?}, received {:?}",
+                event.platform,
+                event.mint,
+                event.name,
+                event.symbol,
+                event.uri,
+                event.signature,
+                event.received_at
             );
             state.seen_mints.insert(event.mint);
         }
-        FilterDecision::Blacklisted => {
-            log::warn!("Developer {} is blacklisted", event.developer);
-            return Ok(());
-        }
-        FilterDecision::NotWhitelisted => {
-            log::info!("Developer {} not whitelisted", event.developer);
+        rejection => {
+            if let FilterDecision::FunderBlacklisted { .. } = rejection {
+                state.filters.spread_blacklist(event.developer);
+            }
+            reject(state, event, &rejection);
             return Ok(());
         }
-        FilterDecision::RateLimited => {
-            log::info!("Developer {} rate limited", event.developer);
+    }
+
+    if event.platform != Platform::PumpFun {
+        log::info!(
+            "Skipping {:?} token {}: buy path only supports pump.fun",
+            event.platform,
+            event.mint
+        );
+        return Ok(());
+    }
+
+    // Started before building so the fetch overlaps it in parallel mode.
+    let metadata = state.metadata.clone().map(|resolver| {
+        let uri = event.uri.clone();
+        tokio::spawn(async move { resolver.resolve(&uri).await })
+    });
+    let mut metadata = metadata.zip(config.metadata.as_ref());
+    if let Some((fetch, metadata_config)) =
+        metadata.take_if(|(_, metadata_config)| metadata_config.mode() == MetadataMode::Blocking)
+    {
+        if !passes_metadata(event, fetch, metadata_config, state).await {
             return Ok(());
         }
-        FilterDecision::Duplicate => return Ok(()),
     }
 
+    let resolved;
+    let event = if event.token_program_inferred {
+        let owner = rpc_client.get_account(&event.mint).await?.owner;
+        if owner != event.token_program {
+            log::debug!("{} is minted under {owner}", event.mint);
+        }
+        resolved = event.clone().with_token_program(owner);
+        &resolved
+    } else {
+        event
+    };
+
     let spend_lamports = config.compute_buy_amount(state.balance_cache.current())?;
     if let Some(transaction) = builder.build_buy_transaction(event, spend_lamports)? {
+        if let Some((fetch, metadata_config)) = metadata {
+            if !passes_metadata(event, fetch, metadata_config, state).await {
+                return Ok(());
+            }
+        }
         if config.dry_run() {
             log::info!(
                 "DRY_RUN: Built buy transaction for mint {} spending {} lamports",
@@ -107,7 +461,10 @@ async fn handle_event(
             return Ok(());
         }
 
-        match dispatch_transaction(&transaction, config, rpc_client, http_client).await {
+        let dispatched =
+            dispatch_transaction(&transaction, config, rpc_client, http_client, latency).await;
+        latency.record(Stage::Total, event.received_instant.elapsed());
+        match dispatched {
             Ok(signature) => {
                 state.balance_cache.debit(spend_lamports);
                 log::info!("Submitted transaction {signature} for mint {}", event.mint);
@@ -118,3 +475,164 @@ async fn handle_event(
 
     Ok(())
 }
+
+/// Waits for the metadata fetch and runs the metadata filters on it.
+async fn passes_metadata(
+    event: &TokenEvent,
+    fetch: JoinHandle<Result<Arc<TokenMetadata>>>,
+    config: &MetadataConfig,
+    state: &state::SniperState,
+) -> bool {
+    let started = Instant::now();
+    let metadata = match fetch
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result)
+    {
+        Ok(metadata) => Some(metadata),
+        Err(err) => {
+            log::info!("No metadata for {} from {}: {err}", event.mint, event.uri);
+            None
+        }
+    };
+    state.latency.record(Stage::Metadata, started.elapsed());
+    match apply_metadata_filters(event, metadata.as_deref(), config, state) {
+        Some(rejection) => {
+            reject(state, event, &rejection);
+            false
+        }
+        None => true,
+    }
+}
+
+/// Logs and records a rejection.
+fn reject(state: &state::SniperState, event: &TokenEvent, decision: &FilterDecision) {
+    state.rejections.record(event, decision);
+    match decision {
+        FilterDecision::Allowed { .. } | FilterDecision::Duplicate => {}
+        FilterDecision::Blacklisted { .. } | FilterDecision::FunderBlacklisted { .. } => {
+            log::warn!("Skipping {} by {}: {decision}", event.mint, event.developer);
+        }
+        _ => log::info!("Skipping {} by {}: {decision}", event.mint, event.developer),
+    }
+}
+
+/// Mirrors a leader's bonding-curve or PumpSwap trade: buys are sized by
+/// `copy_trade.size_ratio`, sells sell the same share of our balance that the
+/// leader sold of theirs.
+async fn handle_copy_trade(
+    config: &Config,
+    state: &state::SniperState,
+    builder: &TransactionBuilder,
+    http_client: &Client,
+    rpc_client: Arc<RpcClient>,
+    owner: &Pubkey,
+    event: &CopyTradeEvent,
+) -> Result<()> {
+    let Some(copy) = &config.copy_trade else {
+        return Ok(());
+    };
+    let side = if event.is_buy() { "buy" } else { "sell" };
+    log::info!(
+        "Leader {} {side}: {} tokens for {} lamports via {:?} at slot {:?} (tx {:?})",
+        event.leader,
+        event.token_amount(),
+        event.sol_amount(),
+        event.source,
+        event.slot,
+        event.signature
+    );
+    let mint = match &event.trade {
+        LeaderTrade::Curve(trade) => trade.mint,
+        LeaderTrade::Swap(swap) => match state.pools.mint(&swap.pool) {
+            Some(mint) => mint,
+            None => {
+                let data = rpc_client.get_account_data(&swap.pool).await?;
+                let Some((mint, quote_mint)) = pump_swap::pool_mints(&data) else {
+                    anyhow::bail!("Pool {} has no readable mints", swap.pool);
+                };
+                if quote_mint != WSOL_MINT {
+                    log::debug!(
+                        "Not mirroring swap on {quote_mint}-quoted pool {}",
+                        swap.pool
+                    );
+                    return Ok(());
+                }
+                state.pools.insert(swap.pool, mint);
+                mint
+            }
+        },
+    };
+
+    let sold_share = state
+        .leaders
+        .apply(event.leader, mint, event.is_buy(), event.token_amount());
+    let token_program = rpc_client.get_account(&mint).await?.owner;
+    let market = match &event.trade {
+        LeaderTrade::Curve(trade) => {
+            let Some(curve) = CurveTrade::from_trade(trade, token_program) else {
+                log::warn!("Trade on {mint} has no creator field; can't mirror it");
+                return Ok(());
+            };
+            Market::Curve(curve)
+        }
+        LeaderTrade::Swap(swap) => Market::Swap(SwapTrade::from_swap(swap, mint, token_program)),
+    };
+
+    let (transaction, spend_lamports) = if event.is_buy() {
+        let Some(lamports) = copy.buy_lamports(event.sol_amount()) else {
+            log::debug!(
+                "Leader buy of {} lamports below copy minimum",
+                event.sol_amount()
+            );
+            return Ok(());
+        };
+        (builder.build_market_buy(&market, lamports)?, lamports)
+    } else {
+        if !copy.mirror_sells() {
+            return Ok(());
+        }
+        let account = pump_fun::associated_token_address(owner, &mint, &token_program);
+        let account = rpc_client
+            .get_account_with_commitment(&account, rpc_client.commitment())
+            .await
+            .with_context(|| format!("Failed to read our {mint} token account"))?
+            .value;
+        // No account is no position. The amount sits at the same offset in
+        // SPL Token and Token-2022 accounts.
+        let balance = match account {
+            Some(account) => account
+                .data
+                .get(64..72)
+                .and_then(|amount| amount.try_into().ok())
+                .map(u64::from_le_bytes)
+                .ok_or_else(|| anyhow!("Our {mint} token account is malformed"))?,
+            None => 0,
+        };
+        let tokens = (balance as f64 * sold_share) as u64;
+        if tokens == 0 {
+            log::debug!("No {mint} position to mirror the sell with");
+            return Ok(());
+        }
+        (builder.build_market_sell(&market, tokens)?, 0)
+    };
+    let Some(transaction) = transaction else {
+        return Ok(());
+    };
+
+    if config.dry_run() {
+        log::info!("DRY_RUN: Built copy {side} transaction for mint {mint}");
+        return Ok(());
+    }
+    let signature = dispatch_transaction(
+        &transaction,
+        config,
+        rpc_client,
+        http_client,
+        &state.latency,
+    )
+    .await?;
+    state.balance_cache.debit(spend_lamports);
+    log::info!("Submitted copy {side} {signature} for mint {mint}");
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::MetadataConfig;
+
+/// Resolved URIs kept in the cache; the oldest are evicted beyond this.
+const CACHE_CAPACITY: usize = 4096;
+
+/// Whether buys wait for the metadata before or after building.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataMode {
+    /// Fetch, check, then build.
+    #[default]
+    Blocking,
+    /// Fetch while the transaction is built and signed; check before
+    /// dispatch.
+    Parallel,
+}
+
+/// The off-chain JSON a token's `uri` points at.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenMetadata {
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub twitter: Option<String>,
+    pub telegram: Option<String>,
+    pub website: Option<String>,
+}
+
+impl TokenMetadata {
+    /// Reads the pump.fun layout, with links at the top level or under
+    /// `extensions`.
+    pub fn from_json(json: &Value) -> Self {
+        let field = |name: &str| {
+            [json.get(name), json.pointer(&format!("/extensions/{name}"))]
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::trim)
+                .find(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            description: field("description"),
+            image: field("image"),
+            twitter: field("twitter"),
+            telegram: field("telegram"),
+            website: field("website"),
+        }
+    }
+
+    pub fn has_socials(&self) -> bool {
+        self.twitter.is_some() || self.telegram.is_some() || self.website.is_some()
+    }
+
+    /// Social and website links, for domain checks.
+    pub fn links(&self) -> impl Iterator<Item = &str> {
+        [&self.twitter, &self.telegram, &self.website]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// The X/Twitter account the token links to, lowercased, from either a
+    /// profile or tweet URL or a bare `@handle`. Community links keep their
+    /// full path so distinct communities don't collide.
+    pub fn twitter_handle(&self) -> Option<String> {
+        let twitter = self.twitter.as_deref()?;
+        let path = match Url::parse(twitter) {
+            Ok(url) => url.path().trim_matches('/').to_string(),
+            Err(_) => twitter.trim_start_matches('@').to_string(),
+        };
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        let handle = match segments.next()? {
+            "i" => path.clone(),
+            handle => handle.to_string(),
+        };
+        Some(handle.to_lowercase())
+    }
+}
+
+/// Host of `link`, lowercased; bare domains are read as `https` URLs.
+pub fn link_domain(link: &str) -> Option<String> {
+    let url = Url::parse(link).or_else(|_| Url::parse(&format!("https://{link}")));
+    Some(url.ok()?.host_str()?.to_lowercase())
+}
+
+struct Cache {
+    entries: DashMap<String, Arc<TokenMetadata>>,
+    order: Mutex<VecDeque<String>>,
+}
+
+/// Fetches token metadata JSON with a strict timeout, caching by URI.
+#[derive(Clone)]
+pub struct MetadataResolver {
+    client: Client,
+    timeout: Duration,
+    ipfs_gateway: String,
+    cache: Arc<Cache>,
+}
+
+impl MetadataResolver {
+    pub fn new(config: &MetadataConfig) -> Self {
+        Self {
+            client: Client::new(),
+            timeout: config.timeout(),
+            ipfs_gateway: config.ipfs_gateway().to_string(),
+            cache: Arc::new(Cache {
+                entries: DashMap::new(),
+                order: Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    /// Rewrites `ipfs://` URIs onto the configured HTTP gateway.
+    pub fn http_url(&self, uri: &str) -> String {
+        match uri.strip_prefix("ipfs://") {
+            Some(path) => format!(
+                "{}/{}",
+                self.ipfs_gateway.trim_end_matches('/'),
+                path.trim_start_matches("ipfs/")
+            ),
+            None => uri.to_string(),
+        }
+    }
+
+    pub async fn resolve(&self, uri: &str) -> Result<Arc<TokenMetadata>> {
+        if let Some(metadata) = self.cache.entries.get(uri) {
+            return Ok(metadata.clone());
+        }
+        let started = Instant::now();
+        let json: Value = self
+            .client
+            .get(self.http_url(uri))
+            .timeout(self.timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .map_err(|err| anyhow!("Invalid metadata JSON at {uri}: {err}"))?;
+        log::debug!("Fetched metadata for {uri} in {:?}", started.elapsed());
+
+        let metadata = Arc::new(TokenMetadata::from_json(&json));
+        self.insert(uri, metadata.clone());
+        Ok(metadata)
+    }
+
+    fn insert(&self, uri: &str, metadata: Arc<TokenMetadata>) {
+        if self
+            .cache
+            .entries
+            .insert(uri.to_string(), metadata)
+            .is_some()
+        {
+            return;
+        }
+        let mut order = self.cache.order.lock();
+        order.push_back(uri.to_string());
+        while order.len() > CACHE_CAPACITY {
+            if let Some(oldest) = order.pop_front() {
+                self.cache.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use serde_json::json;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Serves `body` as JSON on a local port, counting requests.
+    async fn serve(body: String, delay: Duration) -> (String, Arc<AtomicU32>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let body = body.clone();
+                tokio::spawn(async move {
+                    let mut request = [0u8; 1024];
+                    let _ = socket.read(&mut request).await;
+                    tokio::time::sleep(delay).await;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        (format!("http://{address}/ipfs/QmToken"), requests)
+    }
+
+    fn resolver(timeout_ms: u64) -> MetadataResolver {
+        MetadataResolver::new(&MetadataConfig {
+            timeout_ms: Some(timeout_ms),
+            ..MetadataConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn fetches_and_caches_social_links() {
+        let body = json!({
+            "name": "Frog",
+            "description": "ribbit",
+            "image": "https://ipfs.io/ipfs/QmImage",
+            "twitter": "https://x.com/FrogCoin/status/1",
+            "website": "frog.example"
+        });
+        let (uri, requests) = serve(body.to_string(), Duration::ZERO).await;
+        let resolver = resolver(2_000);
+
+        let metadata = resolver.resolve(&uri).await.unwrap();
+        assert_eq!(metadata.description.as_deref(), Some("ribbit"));
+        assert_eq!(metadata.telegram, None);
+        assert!(metadata.has_socials());
+        assert_eq!(metadata.twitter_handle().as_deref(), Some("frogcoin"));
+        let domains: Vec<_> = metadata.links().filter_map(link_domain).collect();
+        assert_eq!(domains, ["x.com", "frog.example"]);
+
+        resolver.resolve(&uri).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn times_out_slow_gateways() {
+        let (uri, _) = serve("{}".to_string(), Duration::from_secs(5)).await;
+        assert!(resolver(100).resolve(&uri).await.is_err());
+    }
+
+    #[test]
+    fn rewrites_ipfs_uris_onto_the_gateway() {
+        let resolver = resolver(100);
+        assert_eq!(
+            resolver.http_url("ipfs://QmToken"),
+            "https://ipfs.io/ipfs/QmToken"
+        );
+        assert_eq!(
+            resolver.http_url("https://cf-ipfs.com/ipfs/QmToken"),
+            "https://cf-ipfs.com/ipfs/QmToken"
+        );
+    }
+}
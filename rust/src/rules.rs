@@ -0,0 +1,603 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    bundles::BundleStats,
+    config::FilterRuleConfig,
+    dev_history::DevProfile,
+    events::{pump_fun, Platform, TokenEvent},
+};
+
+/// What a rule does when its condition holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Accept the token, skipping the remaining rules.
+    Allow,
+    /// Reject the token.
+    Reject,
+    /// Reject the token unless the condition holds.
+    Require,
+}
+
+/// The rule that settled a token, or `None` when no rule did.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleVerdict {
+    Allowed(String),
+    Rejected(String),
+}
+
+/// A named condition over a `TokenEvent`, compiled from config.
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    pub action: RuleAction,
+    condition: Expr,
+}
+
+impl Rule {
+    pub fn compile(config: &FilterRuleConfig) -> Result<Self> {
+        let condition = parse(&config.when)
+            .map_err(|err| anyhow!("Invalid filter rule {:?}: {err}", config.name))?;
+        Ok(Self {
+            name: config.name.clone(),
+            action: config.action,
+            condition,
+        })
+    }
+}
+
+/// Values a rule can see besides the event itself.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RuleContext {
+    /// Newest slot any source has reported.
+    pub latest_slot: Option<u64>,
+    /// The developer's launch history, when it is tracked.
+    pub dev: Option<DevProfile>,
+    /// Time since the developer wallet's first transaction, when profiled.
+    pub wallet_age: Option<Duration>,
+    /// The developer wallet's first funder, when profiled.
+    pub funder: Option<Pubkey>,
+    /// Buys in the create's slot, when tracked.
+    pub bundle: Option<BundleStats>,
+}
+
+/// Runs `rules` in order. The first `allow` that holds, or the first
+/// `reject` or `require` that fails the token, settles it.
+pub fn evaluate(rules: &[Rule], event: &TokenEvent, context: RuleContext) -> Option<RuleVerdict> {
+    for rule in rules {
+        let holds = rule.condition.eval(event, context);
+        match rule.action {
+            RuleAction::Allow if holds => return Some(RuleVerdict::Allowed(rule.name.clone())),
+            RuleAction::Reject if holds => return Some(RuleVerdict::Rejected(rule.name.clone())),
+            RuleAction::Require if !holds => return Some(RuleVerdict::Rejected(rule.name.clone())),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Name,
+    Symbol,
+    Uri,
+    Developer,
+    Platform,
+    TokenProgram,
+    InitialBuySol,
+    DevSupplyPct,
+    SlotAge,
+    DevLaunches,
+    DevGraduated,
+    DevRugRate,
+    WalletAge,
+    Funder,
+    SameSlotBuyers,
+    SameSlotSupplyPct,
+}
+
+impl Field {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "name" => Field::Name,
+            "symbol" => Field::Symbol,
+            "uri" => Field::Uri,
+            "developer" => Field::Developer,
+            "platform" => Field::Platform,
+            "token_program" => Field::TokenProgram,
+            "initial_buy_sol" => Field::InitialBuySol,
+            "dev_supply_pct" => Field::DevSupplyPct,
+            "slot_age" => Field::SlotAge,
+            "dev_launches" => Field::DevLaunches,
+            "dev_graduated" => Field::DevGraduated,
+            "dev_rug_rate" => Field::DevRugRate,
+            "wallet_age" => Field::WalletAge,
+            "funder" => Field::Funder,
+            "same_slot_buyers" => Field::SameSlotBuyers,
+            "same_slot_supply_pct" => Field::SameSlotSupplyPct,
+            other => bail!("unknown field {other:?}"),
+        })
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            Field::InitialBuySol
+                | Field::DevSupplyPct
+                | Field::SlotAge
+                | Field::DevLaunches
+                | Field::DevGraduated
+                | Field::DevRugRate
+                | Field::WalletAge
+                | Field::SameSlotBuyers
+                | Field::SameSlotSupplyPct
+        )
+    }
+
+    fn text(self, event: &TokenEvent, context: RuleContext) -> String {
+        match self {
+            Field::Name => event.name.clone(),
+            Field::Symbol => event.symbol.clone(),
+            Field::Uri => event.uri.clone(),
+            Field::Developer => event.developer.to_string(),
+            Field::Funder => context
+                .funder
+                .map(|funder| funder.to_string())
+                .unwrap_or_default(),
+            Field::Platform => match event.platform {
+                Platform::PumpFun => "pump_fun".to_string(),
+                Platform::LetsBonk => "lets_bonk".to_string(),
+            },
+            Field::TokenProgram => {
+                if event.token_program == pump_fun::TOKEN_2022_PROGRAM_ID {
+                    "token_2022".to_string()
+                } else if event.token_program == pump_fun::TOKEN_PROGRAM_ID {
+                    "spl_token".to_string()
+                } else {
+                    event.token_program.to_string()
+                }
+            }
+            Field::InitialBuySol
+            | Field::DevSupplyPct
+            | Field::SlotAge
+            | Field::DevLaunches
+            | Field::DevGraduated
+            | Field::DevRugRate
+            | Field::WalletAge
+            | Field::SameSlotBuyers
+            | Field::SameSlotSupplyPct => String::new(),
+        }
+    }
+
+    /// `None` when the source didn't report the value.
+    fn number(self, event: &TokenEvent, context: RuleContext) -> Option<f64> {
+        match self {
+            Field::InitialBuySol => event.initial_buy.map(|buy| buy.sol()),
+            Field::DevSupplyPct => event.initial_buy.map(|buy| buy.supply_pct()),
+            Field::SlotAge => {
+                let age = context.latest_slot?.saturating_sub(event.slot?);
+                Some(age as f64)
+            }
+            // The launch being filtered is already recorded.
+            Field::DevLaunches => Some(context.dev?.launches.saturating_sub(1) as f64),
+            Field::DevGraduated => Some(context.dev?.graduated as f64),
+            Field::DevRugRate => context.dev?.rug_rate_pct(),
+            Field::WalletAge => Some(context.wallet_age?.as_secs_f64()),
+            Field::SameSlotBuyers => Some(context.bundle?.buyers as f64),
+            Field::SameSlotSupplyPct => Some(context.bundle?.supply_pct),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Literal {
+    Text(String),
+    Number(f64),
+}
+
+#[derive(Debug)]
+enum Predicate {
+    Text(Field, Comparison, String),
+    Number(Field, Comparison, f64),
+    Matches(Field, Regex),
+    Contains(Field, String),
+    In(Field, Vec<Literal>),
+}
+
+impl Predicate {
+    fn eval(&self, event: &TokenEvent, context: RuleContext) -> bool {
+        match self {
+            Predicate::Text(field, comparison, value) => {
+                let text = field.text(event, context);
+                match comparison {
+                    Comparison::Eq => text == *value,
+                    _ => text != *value,
+                }
+            }
+            Predicate::Number(field, comparison, value) => {
+                let Some(number) = field.number(event, context) else {
+                    return false;
+                };
+                match comparison {
+                    Comparison::Eq => number == *value,
+                    Comparison::Ne => number != *value,
+                    Comparison::Lt => number < *value,
+                    Comparison::Le => number <= *value,
+                    Comparison::Gt => number > *value,
+                    Comparison::Ge => number >= *value,
+                }
+            }
+            Predicate::Matches(field, regex) => regex.is_match(&field.text(event, context)),
+            Predicate::Contains(field, needle) => field
+                .text(event, context)
+                .to_lowercase()
+                .contains(&needle.to_lowercase()),
+            Predicate::In(field, values) => {
+                if field.is_numeric() {
+                    let Some(number) = field.number(event, context) else {
+                        return false;
+                    };
+                    values.contains(&Literal::Number(number))
+                } else {
+                    values.contains(&Literal::Text(field.text(event, context)))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Predicate(Predicate),
+}
+
+impl Expr {
+    fn eval(&self, event: &TokenEvent, context: RuleContext) -> bool {
+        match self {
+            Expr::And(left, right) => left.eval(event, context) && right.eval(event, context),
+            Expr::Or(left, right) => left.eval(event, context) || right.eval(event, context),
+            Expr::Not(inner) => !inner.eval(event, context),
+            Expr::Predicate(predicate) => predicate.eval(event, context),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Text(String),
+    Number(f64),
+    Op(Comparison),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '[' | ']' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    _ => Token::Comma,
+                });
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.next_if_eq(&'=').is_some();
+                tokens.push(Token::Op(match (c, equals) {
+                    ('=', true) => Comparison::Eq,
+                    ('!', true) => Comparison::Ne,
+                    ('<', false) => Comparison::Lt,
+                    ('<', true) => Comparison::Le,
+                    ('>', false) => Comparison::Gt,
+                    ('>', true) => Comparison::Ge,
+                    _ => bail!("unexpected {c:?}"),
+                }));
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '\\')) => text.push(escaped),
+                            Some(other) => {
+                                text.push('\\');
+                                text.push(other);
+                            }
+                            None => bail!("unterminated string"),
+                        },
+                        Some(other) => text.push(other),
+                        None => bail!("unterminated string"),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || "-._".contains(*c)) {
+                    if c != '_' {
+                        number.push(c);
+                    }
+                }
+                let value = number
+                    .parse()
+                    .map_err(|_| anyhow!("invalid number {number:?}"))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            other => bail!("unexpected {other:?}"),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a condition such as `symbol matches "(?i)cat" and not
+/// initial_buy_sol >= 2`. `not` binds tightest, then `and`, then `or`.
+fn parse(source: &str) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+    let expr = parser.or()?;
+    if let Some(token) = parser.peek() {
+        bail!("unexpected {token:?} after condition");
+    }
+    Ok(expr)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| anyhow!("condition ends early"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == word) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let token = self.next()?;
+        if token != expected {
+            bail!("expected {expected:?}, found {token:?}");
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.position += 1;
+            let expr = self.or()?;
+            self.expect(Token::RParen)?;
+            return Ok(expr);
+        }
+        self.predicate().map(Expr::Predicate)
+    }
+
+    fn predicate(&mut self) -> Result<Predicate> {
+        let field = match self.next()? {
+            Token::Ident(name) => Field::parse(&name)?,
+            other => bail!("expected a field, found {other:?}"),
+        };
+        match self.next()? {
+            Token::Op(comparison) => match (self.literal()?, field.is_numeric()) {
+                (Literal::Number(value), true) => Ok(Predicate::Number(field, comparison, value)),
+                (Literal::Text(value), false)
+                    if matches!(comparison, Comparison::Eq | Comparison::Ne) =>
+                {
+                    Ok(Predicate::Text(field, comparison, value))
+                }
+                (literal, _) => bail!("can't compare {field:?} {comparison:?} {literal:?}"),
+            },
+            Token::Ident(op) if op == "matches" && !field.is_numeric() => match self.next()? {
+                Token::Text(pattern) => Ok(Predicate::Matches(field, Regex::new(&pattern)?)),
+                other => bail!("expected a pattern, found {other:?}"),
+            },
+            Token::Ident(op) if op == "contains" && !field.is_numeric() => match self.next()? {
+                Token::Text(needle) => Ok(Predicate::Contains(field, needle)),
+                other => bail!("expected a string, found {other:?}"),
+            },
+            Token::Ident(op) if op == "in" => {
+                self.expect(Token::LBracket)?;
+                let mut values = vec![self.literal()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.position += 1;
+                    values.push(self.literal()?);
+                }
+                self.expect(Token::RBracket)?;
+                if let Some(literal) = values
+                    .iter()
+                    .find(|value| matches!(value, Literal::Number(_)) != field.is_numeric())
+                {
+                    bail!("can't match {field:?} in {literal:?}");
+                }
+                Ok(Predicate::In(field, values))
+            }
+            other => bail!("unsupported operator {other:?} for {field:?}"),
+        }
+    }
+
+    fn literal(&mut self) -> Result<Literal> {
+        match self.next()? {
+            Token::Text(text) => Ok(Literal::Text(text)),
+            Token::Number(number) => Ok(Literal::Number(number)),
+            other => bail!("expected a value, found {other:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::InitialBuy;
+
+    fn event(symbol: &str, initial_buy_sol: Option<f64>) -> TokenEvent {
+        TokenEvent {
+            name: "Cool Cat".to_string(),
+            symbol: symbol.to_string(),
+            uri: "https://ipfs.io/ipfs/cat".to_string(),
+            token_program: pump_fun::TOKEN_2022_PROGRAM_ID,
+            slot: Some(100),
+            initial_buy: initial_buy_sol.map(|sol| InitialBuy {
+                lamports: (sol * 1e9) as u64,
+                tokens: 0,
+            }),
+            ..TokenEvent::for_test(Pubkey::new_unique(), Pubkey::new_unique())
+        }
+    }
+
+    fn rule(name: &str, action: RuleAction, when: &str) -> Rule {
+        Rule::compile(&FilterRuleConfig {
+            name: name.to_string(),
+            action,
+            when: when.to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn evaluates_boolean_conditions() {
+        let condition = parse(
+            r#"symbol matches "(?i)cat" and not (initial_buy_sol >= 2 or platform == "lets_bonk")"#,
+        )
+        .unwrap();
+        let context = RuleContext::default();
+        assert!(condition.eval(&event("CAT", Some(1.5)), context));
+        assert!(!condition.eval(&event("CAT", Some(2.0)), context));
+        assert!(!condition.eval(&event("DOG", Some(1.5)), context));
+
+        let condition = parse(r#"token_program in ["token_2022"] and slot_age <= 5"#).unwrap();
+        let fresh = RuleContext {
+            latest_slot: Some(104),
+            ..RuleContext::default()
+        };
+        assert!(condition.eval(&event("CAT", None), fresh));
+        assert!(!condition.eval(&event("CAT", None), RuleContext::default()));
+    }
+
+    #[test]
+    fn first_deciding_rule_wins() {
+        let rules = vec![
+            rule("vip", RuleAction::Allow, r#"name contains "cool""#),
+            rule(
+                "cats_only",
+                RuleAction::Require,
+                r#"symbol matches "(?i)cat""#,
+            ),
+            rule("whales", RuleAction::Reject, "initial_buy_sol > 2"),
+        ];
+        let context = RuleContext::default();
+
+        let mut vip = event("DOG", Some(5.0));
+        assert_eq!(
+            evaluate(&rules, &vip, context),
+            Some(RuleVerdict::Allowed("vip".to_string()))
+        );
+        vip.name = "Plain".to_string();
+        assert_eq!(
+            evaluate(&rules, &vip, context),
+            Some(RuleVerdict::Rejected("cats_only".to_string()))
+        );
+        let mut whale = event("CAT", Some(5.0));
+        whale.name = "Plain".to_string();
+        assert_eq!(
+            evaluate(&rules, &whale, context),
+            Some(RuleVerdict::Rejected("whales".to_string()))
+        );
+        // Missing values never satisfy a comparison.
+        whale.initial_buy = None;
+        assert_eq!(evaluate(&rules, &whale, context), None);
+    }
+
+    #[test]
+    fn rejects_malformed_conditions() {
+        for source in [
+            "symbol",
+            "symbol < \"a\"",
+            "initial_buy_sol matches \"1\"",
+            "mint == \"x\"",
+            "(symbol == \"a\"",
+            "symbol == \"a\" symbol",
+            "name matches \"(\"",
+            "initial_buy_sol in [\"a\"]",
+            "symbol in [\"CAT\", 1]",
+        ] {
+            assert!(parse(source).is_err(), "{source}");
+        }
+    }
+}
@@ -1,13 +1,34 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
 
+use anyhow::{anyhow, Context};
 use dashmap::{DashMap, DashSet};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{hash::Hash, pubkey::Pubkey};
 use tokio::sync::watch;
 use tokio::time::Instant;
 
-use crate::config::Config;
+use crate::{
+    bundles::BundleTracker,
+    config::Config,
+    dev_history::DevHistory,
+    dev_profiler::DevProfiler,
+    events::pump_fun::TradeEvent,
+    filters::RejectionLog,
+    latency::LatencyStats,
+    metadata::MetadataResolver,
+    rules::{Rule, RuleContext},
+};
 
 #[derive(Clone, Debug)]
 pub struct BlockhashCache {
@@ -25,9 +46,10 @@ impl BlockhashCache {
     }
 
     pub fn latest(&self) -> Option<Hash> {
-        self.inner.read().clone()
+        *self.inner.read()
     }
 
+    #[allow(dead_code)]
     pub fn subscribe(&self) -> watch::Receiver<Option<Hash>> {
         self.notifier.subscribe()
     }
@@ -61,12 +83,14 @@ pub struct DevRateLimiter {
 }
 
 impl DevRateLimiter {
-    pub fn is_allowed(&self, developer: &Pubkey, limit: u32, window: Duration) -> bool {
+    /// Records a launch by `developer`; returns their launches within
+    /// `window`, this one included.
+    pub fn record(&self, developer: &Pubkey, window: Duration) -> u32 {
         let mut entry = self.counts.entry(*developer).or_default();
         let now = Instant::now();
         entry.retain(|ts| now.duration_since(*ts) <= window);
         entry.push(now);
-        entry.len() as u32 <= limit
+        entry.len() as u32
     }
 }
 
@@ -98,19 +122,209 @@ impl BalanceCache {
     }
 }
 
+/// Where a list entry came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSource {
+    Config,
+    /// The list file, which runtime additions are written to.
+    File,
+    /// Added at runtime to a list without a file.
+    Runtime,
+}
+
+/// A developer list: config entries plus an optional file of one pubkey per
+/// line. A changed file is read whole and swapped in at once; additions and
+/// removals made at runtime are written back to the file.
+#[derive(Clone, Debug)]
+pub struct DevList {
+    entries: Arc<RwLock<Arc<DashSet<Pubkey>>>>,
+    fixed: Arc<Vec<Pubkey>>,
+    path: Option<PathBuf>,
+    modified: Arc<Mutex<Option<SystemTime>>>,
+}
+
+impl DevList {
+    pub fn new(fixed: Vec<Pubkey>, path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let list = Self {
+            entries: Arc::new(RwLock::new(Arc::new(fixed.iter().copied().collect()))),
+            fixed: Arc::new(fixed),
+            path,
+            modified: Arc::new(Mutex::new(None)),
+        };
+        list.reload()?;
+        Ok(list)
+    }
+
+    fn current(&self) -> Arc<DashSet<Pubkey>> {
+        self.entries.read().clone()
+    }
+
+    pub fn contains(&self, developer: &Pubkey) -> bool {
+        self.current().contains(developer)
+    }
+
+    /// Where `developer`'s entry came from, if listed.
+    pub fn source(&self, developer: &Pubkey) -> Option<ListSource> {
+        if !self.contains(developer) {
+            return None;
+        }
+        Some(if self.fixed.contains(developer) {
+            ListSource::Config
+        } else if self.path.is_some() {
+            ListSource::File
+        } else {
+            ListSource::Runtime
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current().is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.current().len()
+    }
+
+    /// Adds `developer`, appending it to the list file. The file is written
+    /// first, so a failed write leaves the list unchanged. Returns whether it
+    /// was new.
+    pub fn insert(&self, developer: Pubkey) -> anyhow::Result<bool> {
+        // Held so a reload can't swap the set between the two writes.
+        let mut last = self.modified.lock();
+        if self.contains(&developer) {
+            return Ok(false);
+        }
+        if let Some(path) = &self.path {
+            let mut raw = read_list_file(path)?;
+            // Hand-edited files often lack the final newline.
+            if !raw.is_empty() && !raw.ends_with('\n') {
+                raw.push('\n');
+            }
+            raw.push_str(&format!("{developer}\n"));
+            // Our own write isn't a change to reload.
+            *last = replace_file(path, &raw)?;
+        }
+        self.current().insert(developer);
+        Ok(true)
+    }
+
+    /// Removes `developer`, rewriting the list file without its line; every
+    /// other line, comments and blanks included, is kept as is. The file is
+    /// written first, so a failed write leaves the list unchanged. Config
+    /// entries come back on the next reload. Returns whether it was listed.
+    pub fn remove(&self, developer: &Pubkey) -> anyhow::Result<bool> {
+        let mut last = self.modified.lock();
+        if let Some(path) = &self.path {
+            let raw = read_list_file(path)?;
+            let target = developer.to_string();
+            let (dropped, kept): (Vec<&str>, Vec<&str>) = raw
+                .lines()
+                .partition(|line| line.split('#').next().unwrap_or_default().trim() == target);
+            if !dropped.is_empty() {
+                let kept: String = kept.iter().map(|line| format!("{line}\n")).collect();
+                *last = replace_file(path, &kept)?;
+            }
+        }
+        Ok(self.current().remove(developer).is_some())
+    }
+
+    /// Re-reads the list file if it changed since the last read and swaps
+    /// the new entries in. Returns whether it did.
+    pub fn reload(&self) -> anyhow::Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        let modified = file_modified(path)?;
+        let mut last = self.modified.lock();
+        if modified.is_none() || *last == modified {
+            return Ok(false);
+        }
+        let entries: DashSet<Pubkey> = self
+            .fixed
+            .iter()
+            .copied()
+            .chain(read_pubkey_file(path)?)
+            .collect();
+        *self.entries.write() = Arc::new(entries);
+        *last = modified;
+        Ok(true)
+    }
+}
+
+/// The list file's contents, empty if it doesn't exist yet.
+fn read_list_file(path: &Path) -> anyhow::Result<String> {
+    match fs::read_to_string(path) {
+        Ok(raw) => Ok(raw),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(anyhow!("Failed to read {}: {err}", path.display())),
+    }
+}
+
+/// Replaces `path` with `contents` through a staging file, so readers never
+/// see it half written. Returns its new modification time.
+fn replace_file(path: &Path, contents: &str) -> anyhow::Result<Option<SystemTime>> {
+    let staging = path.with_extension("tmp");
+    fs::write(&staging, contents)
+        .with_context(|| format!("Failed to write {}", staging.display()))?;
+    fs::rename(&staging, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    file_modified(path)
+}
+
+/// When `path` was last written, `None` if it doesn't exist.
+fn file_modified(path: &Path) -> anyhow::Result<Option<SystemTime>> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(Some(metadata.modified()?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Pubkeys in `path`, one per line; blank lines and `#` comments are skipped.
+fn read_pubkey_file(path: &Path) -> anyhow::Result<Vec<Pubkey>> {
+    let raw =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    raw.lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| {
+            Pubkey::from_str(line)
+                .map_err(|err| anyhow!("{}:{}: invalid pubkey: {err}", path.display(), index + 1))
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct FilterState {
-    pub whitelist: DashSet<Pubkey>,
-    pub blacklist: DashSet<Pubkey>,
+    pub whitelist: DevList,
+    pub blacklist: DevList,
+    pub rules: Arc<Vec<Rule>>,
+    /// First mint seen linking each twitter account.
+    pub twitter_handles: Arc<DashMap<String, Pubkey>>,
 }
 
 impl FilterState {
     pub fn new(config: &Config) -> anyhow::Result<Self> {
-        let whitelist = config.whitelist()?.into_iter().collect();
-        let blacklist = config.blacklist()?.into_iter().collect();
+        let lists = &config.dev_filters;
+        let whitelist = DevList::new(
+            config.whitelist()?,
+            lists.dev_whitelist_file.as_ref().map(PathBuf::from),
+        )?;
+        let blacklist = DevList::new(
+            config.blacklist()?,
+            lists.dev_blacklist_file.as_ref().map(PathBuf::from),
+        )?;
+        let rules = config
+            .filter_rules
+            .iter()
+            .map(Rule::compile)
+            .collect::<anyhow::Result<_>>()?;
         Ok(Self {
             whitelist,
             blacklist,
+            rules: Arc::new(rules),
+            twitter_handles: Arc::new(DashMap::new()),
         })
     }
 
@@ -121,15 +335,261 @@ impl FilterState {
     pub fn is_blacklisted(&self, developer: &Pubkey) -> bool {
         self.blacklist.contains(developer)
     }
+
+    pub fn blacklist_source(&self, developer: &Pubkey) -> Option<ListSource> {
+        self.blacklist.source(developer)
+    }
+
+    /// Blacklists a developer funded by a blacklisted wallet, so the wallets
+    /// it funds are caught the same way. The list file is written on a
+    /// blocking thread.
+    pub fn spread_blacklist(&self, developer: Pubkey) -> tokio::task::JoinHandle<()> {
+        let blacklist = self.blacklist.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(err) = blacklist.insert(developer) {
+                log::warn!("Failed to persist blacklisted {developer}: {err:#}");
+            }
+        })
+    }
+
+    /// Polls the list files, swapping in any that changed.
+    pub fn spawn_reloader(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let filters = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                for (name, list) in [
+                    ("whitelist", &filters.whitelist),
+                    ("blacklist", &filters.blacklist),
+                ] {
+                    match list.reload() {
+                        Ok(true) => log::info!("Reloaded dev {name}: {} entries", list.len()),
+                        Ok(false) => {}
+                        Err(err) => log::warn!("Failed to reload dev {name}: {err:#}"),
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Live bonding-curve state for one mint, folded from its trade events.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Market {
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub buys: u64,
+    pub sells: u64,
+    pub buy_volume_lamports: u64,
+    pub sell_volume_lamports: u64,
+    /// On-chain timestamp of the latest trade applied.
+    pub last_trade_at: i64,
+    pub last_slot: Option<u64>,
+}
+
+impl Market {
+    /// Spot price in SOL per whole token (9 SOL decimals, 6 token decimals).
+    pub fn price_sol(&self) -> f64 {
+        if self.virtual_token_reserves == 0 {
+            return 0.0;
+        }
+        (self.virtual_sol_reserves as f64 / 1e9) / (self.virtual_token_reserves as f64 / 1e6)
+    }
+}
+
+/// Per-mint market state keyed by mint, updated from the trade stream.
+#[derive(Clone, Debug, Default)]
+pub struct MarketState {
+    markets: Arc<DashMap<Pubkey, Market>>,
+}
+
+impl MarketState {
+    pub fn apply(&self, trade: &TradeEvent, slot: Option<u64>) {
+        let mut market = self.markets.entry(trade.mint).or_default();
+        if trade.is_buy {
+            market.buys += 1;
+            market.buy_volume_lamports += trade.sol_amount;
+        } else {
+            market.sells += 1;
+            market.sell_volume_lamports += trade.sol_amount;
+        }
+        // Sources can deliver out of order; reserves only move forward.
+        if trade.timestamp >= market.last_trade_at {
+            market.virtual_sol_reserves = trade.virtual_sol_reserves;
+            market.virtual_token_reserves = trade.virtual_token_reserves;
+            market.real_sol_reserves = trade.real_sol_reserves;
+            market.real_token_reserves = trade.real_token_reserves;
+            market.last_trade_at = trade.timestamp;
+            market.last_slot = slot.or(market.last_slot);
+        }
+    }
+
+    pub fn get(&self, mint: &Pubkey) -> Option<Market> {
+        self.markets.get(mint).map(|market| market.clone())
+    }
+
+    /// Drops mints whose last trade is older than `max_age_secs` before `now`
+    /// (both Unix seconds).
+    pub fn prune(&self, now: i64, max_age_secs: i64) {
+        self.markets
+            .retain(|_, market| now - market.last_trade_at <= max_age_secs);
+    }
+
+    pub fn len(&self) -> usize {
+        self.markets.len()
+    }
+}
+
+/// The mint behind every PumpSwap pool seen, from migrations or looked up
+/// for a leader's swap.
+#[derive(Clone, Debug, Default)]
+pub struct PoolMints {
+    mints: Arc<DashMap<Pubkey, Pubkey>>,
+}
+
+impl PoolMints {
+    /// Records `pool` as `mint`'s; `false` if it was already known.
+    pub fn insert(&self, pool: Pubkey, mint: Pubkey) -> bool {
+        self.mints.insert(pool, mint).is_none()
+    }
+
+    pub fn mint(&self, pool: &Pubkey) -> Option<Pubkey> {
+        self.mints.get(pool).map(|mint| *mint)
+    }
+}
+
+/// Token balances of copy-trading leaders, as far as their observed trades
+/// tell. Mirrored sells are sized as a share of these.
+#[derive(Clone, Debug, Default)]
+pub struct LeaderBook {
+    holdings: Arc<DashMap<(Pubkey, Pubkey), u64>>,
+}
+
+impl LeaderBook {
+    /// Records a leader trade. For sells, returns the share of the leader's
+    /// known position that was sold, or `1.0` if the buys weren't seen.
+    pub fn apply(&self, leader: Pubkey, mint: Pubkey, is_buy: bool, tokens: u64) -> f64 {
+        let mut held = self.holdings.entry((leader, mint)).or_default();
+        if is_buy {
+            *held += tokens;
+            return 0.0;
+        }
+        let share = if *held == 0 {
+            1.0
+        } else {
+            (tokens as f64 / *held as f64).min(1.0)
+        };
+        *held = held.saturating_sub(tokens);
+        share
+    }
+}
+
+/// Newest slot any source reported; ages events by slot.
+#[derive(Clone, Debug, Default)]
+pub struct SlotClock {
+    latest: Arc<AtomicU64>,
+}
+
+impl SlotClock {
+    pub fn observe(&self, slot: u64) {
+        self.latest.fetch_max(slot, Ordering::Relaxed);
+    }
+
+    pub fn latest(&self) -> Option<u64> {
+        Some(self.latest.load(Ordering::Relaxed)).filter(|slot| *slot > 0)
+    }
+
+    pub fn rule_context(&self) -> RuleContext {
+        RuleContext {
+            latest_slot: self.latest(),
+            dev: None,
+            wallet_age: None,
+            funder: None,
+            bundle: None,
+        }
+    }
+}
+
+/// Mints with a pipeline task running, so no mint is processed twice at once.
+#[derive(Clone, Debug, Default)]
+pub struct InFlight {
+    mints: Arc<DashSet<Pubkey>>,
+}
+
+impl InFlight {
+    /// `None` while another task holds `mint`; the guard releases it on drop.
+    pub fn claim(&self, mint: Pubkey) -> Option<InFlightGuard> {
+        self.mints.insert(mint).then(|| InFlightGuard {
+            mints: self.mints.clone(),
+            mint,
+        })
+    }
+}
+
+pub struct InFlightGuard {
+    mints: Arc<DashSet<Pubkey>>,
+    mint: Pubkey,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.mints.remove(&self.mint);
+    }
+}
+
+/// Outcomes of per-event pipeline tasks.
+#[derive(Clone, Debug, Default)]
+pub struct PipelineStats {
+    processed: Arc<AtomicU64>,
+    failed: Arc<AtomicU64>,
+}
+
+impl PipelineStats {
+    pub fn record(&self, ok: bool) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn processed(&self) -> u64 {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Clone)]
 pub struct SniperState {
     pub filters: FilterState,
     pub rate_limiter: DevRateLimiter,
+    /// Filter outcomes by reason and the latest rejections.
+    pub rejections: RejectionLog,
     pub seen_mints: DashSet<Pubkey>,
+    pub in_flight: InFlight,
+    pub slots: SlotClock,
+    pub pipeline: PipelineStats,
+    pub markets: MarketState,
+    /// Mint of every PumpSwap pool seen, graduations included, for trading
+    /// after migration.
+    pub pools: PoolMints,
+    pub leaders: LeaderBook,
     pub blockhash_cache: BlockhashCache,
     pub balance_cache: BalanceCache,
+    /// Hot-path stage timings, logged periodically.
+    pub latency: LatencyStats,
+    /// Set when `[metadata]` is configured.
+    pub metadata: Option<MetadataResolver>,
+    /// Set when `[dev_history]` is configured.
+    pub dev_history: Option<DevHistory>,
+    /// Set when `[bundles]` is configured.
+    pub bundles: Option<BundleTracker>,
+    /// Set when `[dev_profiler]` is configured.
+    pub dev_profiler: Option<DevProfiler>,
     pub rpc_client: Arc<RpcClient>,
 }
 
@@ -138,9 +598,29 @@ impl SniperState {
         Ok(Self {
             filters: FilterState::new(config)?,
             rate_limiter: DevRateLimiter::default(),
+            rejections: RejectionLog::new(config.recent_rejections()),
             seen_mints: DashSet::new(),
+            in_flight: InFlight::default(),
+            slots: SlotClock::default(),
+            pipeline: PipelineStats::default(),
+            markets: MarketState::default(),
+            pools: PoolMints::default(),
+            leaders: LeaderBook::default(),
             blockhash_cache: BlockhashCache::new(),
             balance_cache: BalanceCache::new(0),
+            latency: LatencyStats::default(),
+            metadata: config.metadata.as_ref().map(MetadataResolver::new),
+            dev_history: config
+                .dev_history
+                .as_ref()
+                .map(DevHistory::open)
+                .transpose()?,
+            bundles: config.bundles.as_ref().map(|_| BundleTracker::default()),
+            dev_profiler: config
+                .dev_profiler
+                .as_ref()
+                .map(|profiler| DevProfiler::new(rpc_client.clone(), profiler))
+                .transpose()?,
             rpc_client,
         })
     }
@@ -152,3 +632,76 @@ impl SniperState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(mint: Pubkey, is_buy: bool, sol_amount: u64, timestamp: i64) -> TradeEvent {
+        TradeEvent {
+            sol_amount,
+            token_amount: 1_000_000,
+            timestamp,
+            virtual_sol_reserves: 30_000_000_000 + timestamp as u64,
+            virtual_token_reserves: 1_073_000_000_000_000,
+            real_sol_reserves: timestamp as u64,
+            real_token_reserves: 793_100_000_000_000,
+            ..TradeEvent::for_test(mint, Pubkey::new_unique(), is_buy)
+        }
+    }
+
+    #[test]
+    fn folds_trades_into_market_state() {
+        let markets = MarketState::default();
+        let mint = Pubkey::new_unique();
+
+        markets.apply(&trade(mint, true, 500, 10), Some(1));
+        markets.apply(&trade(mint, false, 200, 12), Some(3));
+        // Late delivery of an older trade counts but doesn't rewind reserves.
+        markets.apply(&trade(mint, true, 100, 11), Some(2));
+
+        let market = markets.get(&mint).unwrap();
+        assert_eq!((market.buys, market.sells), (2, 1));
+        assert_eq!(market.buy_volume_lamports, 600);
+        assert_eq!(market.sell_volume_lamports, 200);
+        assert_eq!(market.real_sol_reserves, 12);
+        assert_eq!(market.last_slot, Some(3));
+        assert!((market.price_sol() - 0.000_000_028).abs() < 1e-9);
+
+        markets.prune(100, 60);
+        assert!(markets.get(&mint).is_none());
+    }
+
+    #[test]
+    fn claims_each_mint_once_until_released() {
+        let in_flight = InFlight::default();
+        let mint = Pubkey::new_unique();
+
+        let guard = in_flight.claim(mint).unwrap();
+        assert!(in_flight.claim(mint).is_none());
+        assert!(in_flight.claim(Pubkey::new_unique()).is_some());
+        drop(guard);
+        assert!(in_flight.claim(mint).is_some());
+    }
+
+    #[test]
+    fn sizes_leader_sells_by_known_position() {
+        let book = LeaderBook::default();
+        let leader = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        assert_eq!(book.apply(leader, mint, false, 10), 1.0);
+        book.apply(leader, mint, true, 1_000);
+        assert_eq!(book.apply(leader, mint, false, 250), 0.25);
+        assert_eq!(book.apply(leader, mint, false, 1_000), 1.0);
+    }
+
+    #[test]
+    fn failed_list_writes_leave_the_list_unchanged() {
+        let path = std::env::temp_dir().join("missing-dir/blacklist.txt");
+        let list = DevList::new(vec![], Some(path)).unwrap();
+        let developer = Pubkey::new_unique();
+        assert!(list.insert(developer).is_err());
+        assert!(!list.contains(&developer));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{str::FromStr, sync::Arc};
+use std::{sync::Arc, time::Instant};
 
 use anyhow::Result;
 use solana_sdk::{
@@ -7,16 +7,117 @@ use solana_sdk::{
     message::Message,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
-    system_program,
+    system_instruction, system_program,
     transaction::Transaction,
 };
 
-use crate::{config::Config, events::TokenEvent, state::BlockhashCache};
+use crate::{
+    config::Config,
+    events::{launchlab::WSOL_MINT, pump_fun, pump_swap, TokenEvent},
+    latency::{LatencyStats, Stage},
+    state::BlockhashCache,
+};
+
+/// SPL Token instruction tags.
+const CLOSE_ACCOUNT: u8 = 9;
+const SYNC_NATIVE: u8 = 17;
+
+/// Reserves of a freshly created pump.fun bonding curve.
+const INITIAL_VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000;
+const INITIAL_VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+
+/// Accounts and last known reserves of one pump.fun bonding curve.
+#[derive(Clone, Debug)]
+pub struct CurveTrade {
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub creator_vault: Pubkey,
+    pub token_program: Pubkey,
+    pub is_mayhem_mode: bool,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+}
+
+impl CurveTrade {
+    /// A fresh curve for a just-created token.
+    pub fn from_token_event(event: &TokenEvent) -> Self {
+        Self {
+            mint: event.mint,
+            bonding_curve: event.bonding_curve,
+            associated_bonding_curve: event.associated_bonding_curve,
+            creator_vault: event
+                .creator_vault
+                .unwrap_or_else(|| pump_fun::creator_vault(&event.creator)),
+            token_program: event.token_program,
+            is_mayhem_mode: event.is_mayhem_mode,
+            virtual_sol_reserves: INITIAL_VIRTUAL_SOL_RESERVES,
+            virtual_token_reserves: INITIAL_VIRTUAL_TOKEN_RESERVES,
+        }
+    }
+
+    /// The curve as a logged trade left it. `None` when the event predates
+    /// the creator field, since the creator vault can't be derived then.
+    pub fn from_trade(trade: &pump_fun::TradeEvent, token_program: Pubkey) -> Option<Self> {
+        let bonding_curve = pump_fun::bonding_curve(&trade.mint);
+        Some(Self {
+            mint: trade.mint,
+            bonding_curve,
+            associated_bonding_curve: pump_fun::associated_token_address(
+                &bonding_curve,
+                &trade.mint,
+                &token_program,
+            ),
+            creator_vault: pump_fun::creator_vault(&trade.creator?),
+            token_program,
+            is_mayhem_mode: trade.is_mayhem_mode(),
+            virtual_sol_reserves: trade.virtual_sol_reserves,
+            virtual_token_reserves: trade.virtual_token_reserves,
+        })
+    }
+}
+
+/// Accounts and last known reserves of one PumpSwap pool quoted in WSOL.
+#[derive(Clone, Debug)]
+pub struct SwapTrade {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub protocol_fee_recipient: Pubkey,
+    pub coin_creator: Pubkey,
+    pub base_reserves: u64,
+    pub quote_reserves: u64,
+}
+
+impl SwapTrade {
+    /// The pool as a logged swap of `mint` left it.
+    pub fn from_swap(swap: &pump_swap::SwapEvent, mint: Pubkey, token_program: Pubkey) -> Self {
+        let (base_reserves, quote_reserves) = swap.reserves_after();
+        Self {
+            pool: swap.pool,
+            mint,
+            token_program,
+            protocol_fee_recipient: swap.protocol_fee_recipient,
+            coin_creator: swap.coin_creator,
+            base_reserves,
+            quote_reserves,
+        }
+    }
+}
+
+/// Where a copied trade is mirrored: the bonding curve, or the PumpSwap
+/// pool after graduation.
+#[derive(Clone, Debug)]
+pub enum Market {
+    Curve(CurveTrade),
+    Swap(SwapTrade),
+}
 
 pub struct TransactionBuilder {
     config: Arc<Config>,
     payer: Arc<Keypair>,
     blockhash_cache: BlockhashCache,
+    latency: LatencyStats,
     program_id: Pubkey,
 }
 
@@ -25,12 +126,14 @@ impl TransactionBuilder {
         config: Arc<Config>,
         payer: Arc<Keypair>,
         blockhash_cache: BlockhashCache,
+        latency: LatencyStats,
     ) -> Result<Self> {
         let program_id = config.program_id()?;
         Ok(Self {
             config,
             payer,
             blockhash_cache,
+            latency,
             program_id,
         })
     }
@@ -40,70 +143,541 @@ impl TransactionBuilder {
         event: &TokenEvent,
         lamports: u64,
     ) -> Result<Option<Transaction>> {
+        self.build_curve_buy(&CurveTrade::from_token_event(event), lamports)
+    }
+
+    pub fn build_curve_buy(
+        &self,
+        curve: &CurveTrade,
+        lamports: u64,
+    ) -> Result<Option<Transaction>> {
+        let started = Instant::now();
+        let instructions = vec![
+            self.create_associated_token_account(&curve.mint, &curve.token_program),
+            self.pump_fun_buy_instruction(curve, lamports),
+        ];
+        self.latency.record(Stage::Build, started.elapsed());
+        self.sign(instructions)
+    }
+
+    pub fn build_curve_sell(&self, curve: &CurveTrade, tokens: u64) -> Result<Option<Transaction>> {
+        let started = Instant::now();
+        let instructions = vec![self.pump_fun_sell_instruction(curve, tokens)];
+        self.latency.record(Stage::Build, started.elapsed());
+        self.sign(instructions)
+    }
+
+    pub fn build_market_buy(&self, market: &Market, lamports: u64) -> Result<Option<Transaction>> {
+        match market {
+            Market::Curve(curve) => self.build_curve_buy(curve, lamports),
+            Market::Swap(swap) => self.build_swap_buy(swap, lamports),
+        }
+    }
+
+    pub fn build_market_sell(&self, market: &Market, tokens: u64) -> Result<Option<Transaction>> {
+        match market {
+            Market::Curve(curve) => self.build_curve_sell(curve, tokens),
+            Market::Swap(swap) => self.build_swap_sell(swap, tokens),
+        }
+    }
+
+    /// Wraps `lamports` into WSOL and spends them on `swap`'s pool, closing
+    /// the WSOL account afterwards to unwrap what's left.
+    pub fn build_swap_buy(&self, swap: &SwapTrade, lamports: u64) -> Result<Option<Transaction>> {
+        let started = Instant::now();
+        let user = self.payer.pubkey();
+        let wsol = pump_swap::wsol_account(&user);
+        let instructions = vec![
+            self.create_associated_token_account(&WSOL_MINT, &pump_fun::TOKEN_PROGRAM_ID),
+            system_instruction::transfer(&user, &wsol, lamports),
+            Instruction {
+                program_id: pump_fun::TOKEN_PROGRAM_ID,
+                accounts: vec![AccountMeta::new(wsol, false)],
+                data: vec![SYNC_NATIVE],
+            },
+            self.create_associated_token_account(&swap.mint, &swap.token_program),
+            self.pump_swap_buy_instruction(swap, lamports),
+            self.close_wsol_account(),
+        ];
+        self.latency.record(Stage::Build, started.elapsed());
+        self.sign(instructions)
+    }
+
+    /// Sells `tokens` into `swap`'s pool and unwraps the WSOL received.
+    pub fn build_swap_sell(&self, swap: &SwapTrade, tokens: u64) -> Result<Option<Transaction>> {
+        let started = Instant::now();
+        let instructions = vec![
+            self.create_associated_token_account(&WSOL_MINT, &pump_fun::TOKEN_PROGRAM_ID),
+            self.pump_swap_sell_instruction(swap, tokens),
+            self.close_wsol_account(),
+        ];
+        self.latency.record(Stage::Build, started.elapsed());
+        self.sign(instructions)
+    }
+
+    /// Prepends the priority fee and signs with a cached blockhash.
+    fn sign(&self, instructions: Vec<Instruction>) -> Result<Option<Transaction>> {
+        let started = Instant::now();
         let Some(blockhash) = self.blockhash_cache.latest() else {
             log::warn!("Blockhash cache empty, skipping transaction");
             return Ok(None);
         };
 
-        let mut instructions = Vec::new();
-
+        let mut all = Vec::with_capacity(instructions.len() + 1);
         if let Some(priority_fee) = self.config.fee_config.priority_fee_lamports {
-            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+            all.push(ComputeBudgetInstruction::set_compute_unit_price(
                 priority_fee,
             ));
         }
+        all.extend(instructions);
 
-        instructions.push(self.create_associated_token_account(&event.mint)?);
-        instructions.push(self.pump_fun_buy_instruction(event, lamports)?);
-
-        let message = Message::new(&instructions, Some(&self.payer.pubkey()));
+        let message = Message::new(&all, Some(&self.payer.pubkey()));
         let transaction = Transaction::new(&[self.payer.as_ref()], message, blockhash);
+        self.latency.record(Stage::Sign, started.elapsed());
         Ok(Some(transaction))
     }
 
-    fn pump_fun_buy_instruction(&self, event: &TokenEvent, lamports: u64) -> Result<Instruction> {
+    /// Builds a `buy_exact_sol_in` spending `lamports`, with the minimum
+    /// output quoted against the curve's reserves less `max_slippage_bps`.
+    fn pump_fun_buy_instruction(&self, curve: &CurveTrade, lamports: u64) -> Instruction {
+        let user = self.payer.pubkey();
+
         let accounts = vec![
-            AccountMeta::new(event.mint, false),
-            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(pump_fun::GLOBAL, false),
+            AccountMeta::new(Self::fee_recipient(curve), false),
+            AccountMeta::new_readonly(curve.mint, false),
+            AccountMeta::new(curve.bonding_curve, false),
+            AccountMeta::new(curve.associated_bonding_curve, false),
+            AccountMeta::new(self.user_token_account(curve), false),
+            AccountMeta::new(user, true),
             AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(curve.token_program, false),
+            AccountMeta::new(curve.creator_vault, false),
+            AccountMeta::new_readonly(pump_fun::EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(self.program_id, false),
+            AccountMeta::new_readonly(pump_fun::global_volume_accumulator(), false),
+            AccountMeta::new(pump_fun::user_volume_accumulator(&user), false),
+            AccountMeta::new_readonly(pump_fun::fee_config(), false),
+            AccountMeta::new_readonly(pump_fun::FEE_PROGRAM_ID, false),
         ];
 
-        let mut data = lamports.to_le_bytes().to_vec();
-        data.extend_from_slice(&event.developer.to_bytes());
+        let mut data = pump_fun::BUY_EXACT_SOL_IN_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&lamports.to_le_bytes());
+        data.extend_from_slice(&self.min_tokens_out(curve, lamports).to_le_bytes());
+        // track_volume: OptionBool(true)
+        data.push(1);
 
-        Ok(Instruction {
+        Instruction {
             program_id: self.program_id,
             accounts,
             data,
-        })
+        }
     }
 
-    fn create_associated_token_account(&self, mint: &Pubkey) -> Result<Instruction> {
-        const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
-        const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+    /// Builds a `sell` of `tokens`, with the minimum SOL quoted against the
+    /// curve's reserves less `max_slippage_bps`.
+    fn pump_fun_sell_instruction(&self, curve: &CurveTrade, tokens: u64) -> Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(pump_fun::GLOBAL, false),
+            AccountMeta::new(Self::fee_recipient(curve), false),
+            AccountMeta::new_readonly(curve.mint, false),
+            AccountMeta::new(curve.bonding_curve, false),
+            AccountMeta::new(curve.associated_bonding_curve, false),
+            AccountMeta::new(self.user_token_account(curve), false),
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(curve.creator_vault, false),
+            AccountMeta::new_readonly(curve.token_program, false),
+            AccountMeta::new_readonly(pump_fun::EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(self.program_id, false),
+            AccountMeta::new_readonly(pump_fun::fee_config(), false),
+            AccountMeta::new_readonly(pump_fun::FEE_PROGRAM_ID, false),
+        ];
 
-        let token_program = Pubkey::from_str(TOKEN_PROGRAM_ID)?;
-        let ata_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID)?;
-        let (ata, _) = Pubkey::find_program_address(
-            &[
-                self.payer.pubkey().as_ref(),
-                token_program.as_ref(),
-                mint.as_ref(),
+        let mut data = pump_fun::SELL_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&tokens.to_le_bytes());
+        data.extend_from_slice(&self.min_sol_out(curve, tokens).to_le_bytes());
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    /// Builds a `buy_exact_quote_in` spending `lamports` of WSOL, with the
+    /// minimum output quoted against the pool's reserves less
+    /// `max_slippage_bps`.
+    fn pump_swap_buy_instruction(&self, swap: &SwapTrade, lamports: u64) -> Instruction {
+        let user = self.payer.pubkey();
+        let mut accounts = self.pump_swap_accounts(swap);
+        accounts.splice(
+            19..19,
+            [
+                AccountMeta::new_readonly(pump_swap::global_volume_accumulator(), false),
+                AccountMeta::new(pump_swap::user_volume_accumulator(&user), false),
             ],
-            &ata_program,
         );
 
-        Ok(Instruction {
-            program_id: ata_program,
+        let min_tokens =
+            self.less_slippage(quote(swap.quote_reserves, swap.base_reserves, lamports));
+        let mut data = pump_swap::BUY_EXACT_QUOTE_IN_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&lamports.to_le_bytes());
+        data.extend_from_slice(&min_tokens.to_le_bytes());
+        // track_volume: OptionBool(true)
+        data.push(1);
+
+        Instruction {
+            program_id: pump_swap::PUMP_SWAP_PROGRAM_ID,
+            accounts,
+            data,
+        }
+    }
+
+    /// Builds a `sell` of `tokens`, with the minimum WSOL quoted against the
+    /// pool's reserves less `max_slippage_bps`.
+    fn pump_swap_sell_instruction(&self, swap: &SwapTrade, tokens: u64) -> Instruction {
+        let min_lamports =
+            self.less_slippage(quote(swap.base_reserves, swap.quote_reserves, tokens));
+        let mut data = pump_swap::SELL_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&tokens.to_le_bytes());
+        data.extend_from_slice(&min_lamports.to_le_bytes());
+
+        Instruction {
+            program_id: pump_swap::PUMP_SWAP_PROGRAM_ID,
+            accounts: self.pump_swap_accounts(swap),
+            data,
+        }
+    }
+
+    /// Accounts of a PumpSwap `sell`; buys add the volume accumulators
+    /// before the fee config.
+    fn pump_swap_accounts(&self, swap: &SwapTrade) -> Vec<AccountMeta> {
+        let user = self.payer.pubkey();
+        let token = pump_fun::TOKEN_PROGRAM_ID;
+        let vault_authority = pump_swap::coin_creator_vault_authority(&swap.coin_creator);
+        vec![
+            AccountMeta::new(swap.pool, false),
+            AccountMeta::new(user, true),
+            AccountMeta::new_readonly(pump_swap::GLOBAL_CONFIG, false),
+            AccountMeta::new_readonly(swap.mint, false),
+            AccountMeta::new_readonly(WSOL_MINT, false),
+            AccountMeta::new(
+                pump_fun::associated_token_address(&user, &swap.mint, &swap.token_program),
+                false,
+            ),
+            AccountMeta::new(pump_swap::wsol_account(&user), false),
+            AccountMeta::new(
+                pump_fun::associated_token_address(&swap.pool, &swap.mint, &swap.token_program),
+                false,
+            ),
+            AccountMeta::new(pump_swap::wsol_account(&swap.pool), false),
+            AccountMeta::new_readonly(swap.protocol_fee_recipient, false),
+            AccountMeta::new(pump_swap::wsol_account(&swap.protocol_fee_recipient), false),
+            AccountMeta::new_readonly(swap.token_program, false),
+            AccountMeta::new_readonly(token, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(pump_fun::ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(pump_swap::EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(pump_swap::PUMP_SWAP_PROGRAM_ID, false),
+            AccountMeta::new(pump_swap::wsol_account(&vault_authority), false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new_readonly(pump_swap::fee_config(), false),
+            AccountMeta::new_readonly(pump_fun::FEE_PROGRAM_ID, false),
+        ]
+    }
+
+    fn fee_recipient(curve: &CurveTrade) -> Pubkey {
+        if curve.is_mayhem_mode {
+            pump_fun::MAYHEM_FEE_RECIPIENT
+        } else {
+            pump_fun::FEE_RECIPIENT
+        }
+    }
+
+    fn min_tokens_out(&self, curve: &CurveTrade, lamports: u64) -> u64 {
+        self.less_slippage(quote(
+            curve.virtual_sol_reserves,
+            curve.virtual_token_reserves,
+            lamports,
+        ))
+    }
+
+    fn min_sol_out(&self, curve: &CurveTrade, tokens: u64) -> u64 {
+        self.less_slippage(quote(
+            curve.virtual_token_reserves,
+            curve.virtual_sol_reserves,
+            tokens,
+        ))
+    }
+
+    /// `0` (no minimum) when `max_slippage_bps` is unset.
+    fn less_slippage(&self, quote: u128) -> u64 {
+        let Some(slippage_bps) = self.config.max_slippage_bps else {
+            return 0;
+        };
+        let kept_bps = 10_000u128.saturating_sub(slippage_bps as u128);
+        (quote * kept_bps / 10_000) as u64
+    }
+
+    fn user_token_account(&self, curve: &CurveTrade) -> Pubkey {
+        pump_fun::associated_token_address(&self.payer.pubkey(), &curve.mint, &curve.token_program)
+    }
+
+    /// Idempotent ATA creation, so a retried buy doesn't fail on an existing
+    /// account.
+    fn create_associated_token_account(
+        &self,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Instruction {
+        let payer = self.payer.pubkey();
+        Instruction {
+            program_id: pump_fun::ASSOCIATED_TOKEN_PROGRAM_ID,
             accounts: vec![
-                AccountMeta::new(self.payer.pubkey(), true),
-                AccountMeta::new(ata, false),
-                AccountMeta::new_readonly(self.payer.pubkey(), false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new(
+                    pump_fun::associated_token_address(&payer, mint, token_program),
+                    false,
+                ),
+                AccountMeta::new_readonly(payer, false),
                 AccountMeta::new_readonly(*mint, false),
                 AccountMeta::new_readonly(system_program::ID, false),
-                AccountMeta::new_readonly(token_program, false),
+                AccountMeta::new_readonly(*token_program, false),
             ],
-            data: vec![],
-        })
+            data: vec![1],
+        }
+    }
+
+    /// Closes the payer's WSOL account, returning its balance as SOL.
+    fn close_wsol_account(&self) -> Instruction {
+        let payer = self.payer.pubkey();
+        Instruction {
+            program_id: pump_fun::TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(pump_swap::wsol_account(&payer), false),
+                AccountMeta::new(payer, false),
+                AccountMeta::new_readonly(payer, true),
+            ],
+            data: vec![CLOSE_ACCOUNT],
+        }
+    }
+}
+
+/// Constant-product output for `amount_in`, before fees.
+fn quote(reserve_in: u64, reserve_out: u64, amount_in: u64) -> u128 {
+    let amount_in = amount_in as u128;
+    reserve_out as u128 * amount_in / (reserve_in as u128 + amount_in)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::hash::Hash;
+
+    use super::*;
+
+    fn test_config() -> Config {
+        toml::from_str(
+            r#"
+            keypair_path = "unused.json"
+            purchase_strategy = { fixed_sol = 0.1 }
+            max_slippage_bps = 500
+
+            [endpoints]
+            rpc_http_url = "http://127.0.0.1:8899"
+
+            [fee_config]
+
+            [dev_filters]
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn token_event(is_mayhem_mode: bool) -> TokenEvent {
+        let mint = Pubkey::new_unique();
+        let bonding_curve = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        TokenEvent {
+            name: "Frog".to_string(),
+            symbol: "FROG".to_string(),
+            uri: "ipfs://frog".to_string(),
+            bonding_curve,
+            associated_bonding_curve: pump_fun::associated_token_address(
+                &bonding_curve,
+                &mint,
+                &pump_fun::TOKEN_2022_PROGRAM_ID,
+            ),
+            creator_vault: Some(pump_fun::creator_vault(&creator)),
+            token_program: pump_fun::TOKEN_2022_PROGRAM_ID,
+            is_mayhem_mode,
+            ..TokenEvent::for_test(mint, creator)
+        }
+    }
+
+    #[test]
+    fn builds_buy_exact_sol_in_from_event_accounts() {
+        let cache = BlockhashCache::new();
+        cache.update(Hash::new_unique());
+        let payer = Arc::new(Keypair::new());
+        let builder = TransactionBuilder::new(
+            Arc::new(test_config()),
+            payer.clone(),
+            cache,
+            LatencyStats::default(),
+        )
+        .unwrap();
+        let event = token_event(true);
+
+        let transaction = builder
+            .build_buy_transaction(&event, 100_000_000)
+            .unwrap()
+            .unwrap();
+        let message = &transaction.message;
+        let buy = message.instructions.last().unwrap();
+        let keys: Vec<Pubkey> = buy
+            .accounts
+            .iter()
+            .map(|index| message.account_keys[*index as usize])
+            .collect();
+
+        assert_eq!(keys.len(), 16);
+        assert_eq!(keys[1], pump_fun::MAYHEM_FEE_RECIPIENT);
+        assert_eq!(keys[3], event.bonding_curve);
+        assert_eq!(keys[4], event.associated_bonding_curve);
+        assert_eq!(
+            keys[5],
+            pump_fun::associated_token_address(&payer.pubkey(), &event.mint, &event.token_program)
+        );
+        assert_eq!(keys[8], pump_fun::TOKEN_2022_PROGRAM_ID);
+        assert_eq!(keys[9], event.creator_vault.unwrap());
+
+        assert_eq!(buy.data[..8], pump_fun::BUY_EXACT_SOL_IN_DISCRIMINATOR);
+        assert_eq!(buy.data[8..16], 100_000_000u64.to_le_bytes());
+        let min_out = u64::from_le_bytes(buy.data[16..24].try_into().unwrap());
+        assert!(min_out > 0 && min_out < 3_450_000_000_000);
+        assert_eq!(buy.data[24..], [1]);
+    }
+
+    #[test]
+    fn builds_sell_against_trade_reserves() {
+        let cache = BlockhashCache::new();
+        cache.update(Hash::new_unique());
+        let payer = Arc::new(Keypair::new());
+        let builder = TransactionBuilder::new(
+            Arc::new(test_config()),
+            payer.clone(),
+            cache,
+            LatencyStats::default(),
+        )
+        .unwrap();
+        let creator = Pubkey::new_unique();
+        let trade = pump_fun::TradeEvent {
+            sol_amount: 1_000_000_000,
+            token_amount: 30_000_000_000_000,
+            virtual_sol_reserves: 40_000_000_000,
+            virtual_token_reserves: 800_000_000_000_000,
+            real_sol_reserves: 10_000_000_000,
+            real_token_reserves: 520_000_000_000_000,
+            fee_recipient: Some(pump_fun::FEE_RECIPIENT),
+            creator: Some(creator),
+            ..pump_fun::TradeEvent::for_test(Pubkey::new_unique(), Pubkey::new_unique(), true)
+        };
+        let curve = CurveTrade::from_trade(&trade, pump_fun::TOKEN_PROGRAM_ID).unwrap();
+        assert!(CurveTrade::from_trade(
+            &pump_fun::TradeEvent {
+                creator: None,
+                ..trade.clone()
+            },
+            pump_fun::TOKEN_PROGRAM_ID
+        )
+        .is_none());
+
+        let transaction = builder
+            .build_curve_sell(&curve, 20_000_000_000_000)
+            .unwrap()
+            .unwrap();
+        let message = &transaction.message;
+        let sell = message.instructions.last().unwrap();
+        let keys: Vec<Pubkey> = sell
+            .accounts
+            .iter()
+            .map(|index| message.account_keys[*index as usize])
+            .collect();
+
+        assert_eq!(keys.len(), 14);
+        assert_eq!(keys[1], pump_fun::FEE_RECIPIENT);
+        assert_eq!(keys[3], pump_fun::bonding_curve(&trade.mint));
+        assert_eq!(keys[8], pump_fun::creator_vault(&creator));
+        assert_eq!(keys[9], pump_fun::TOKEN_PROGRAM_ID);
+
+        assert_eq!(sell.data[..8], pump_fun::SELL_DISCRIMINATOR);
+        assert_eq!(sell.data[8..16], 20_000_000_000_000u64.to_le_bytes());
+        // 40 SOL * 20M / 820M tokens, less 5% slippage.
+        let min_out = u64::from_le_bytes(sell.data[16..24].try_into().unwrap());
+        assert_eq!(min_out, 926_829_268);
+    }
+
+    #[test]
+    fn wraps_sol_around_pump_swap_buy() {
+        let cache = BlockhashCache::new();
+        cache.update(Hash::new_unique());
+        let payer = Arc::new(Keypair::new());
+        let builder = TransactionBuilder::new(
+            Arc::new(test_config()),
+            payer.clone(),
+            cache,
+            LatencyStats::default(),
+        )
+        .unwrap();
+        let coin_creator = Pubkey::new_unique();
+        let swap = SwapTrade {
+            pool: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            token_program: pump_fun::TOKEN_2022_PROGRAM_ID,
+            protocol_fee_recipient: Pubkey::new_unique(),
+            coin_creator,
+            base_reserves: 200_000_000_000_000,
+            quote_reserves: 50_000_000_000,
+        };
+
+        let transaction = builder
+            .build_swap_buy(&swap, 1_000_000_000)
+            .unwrap()
+            .unwrap();
+        let message = &transaction.message;
+        let program = |index: usize| message.program_id(index).copied();
+        assert_eq!(message.instructions.len(), 6);
+        assert_eq!(program(1), Some(system_program::ID));
+        assert_eq!(program(4), Some(pump_swap::PUMP_SWAP_PROGRAM_ID));
+        assert_eq!(message.instructions[5].data, [CLOSE_ACCOUNT]);
+
+        let buy = &message.instructions[4];
+        let keys: Vec<Pubkey> = buy
+            .accounts
+            .iter()
+            .map(|index| message.account_keys[*index as usize])
+            .collect();
+        assert_eq!(keys.len(), 23);
+        assert_eq!(keys[0], swap.pool);
+        assert_eq!(keys[6], pump_swap::wsol_account(&payer.pubkey()));
+        assert_eq!(keys[11], pump_fun::TOKEN_2022_PROGRAM_ID);
+        assert_eq!(
+            keys[18],
+            pump_swap::coin_creator_vault_authority(&coin_creator)
+        );
+        assert_eq!(keys[19], pump_swap::global_volume_accumulator());
+        assert_eq!(keys[21], pump_swap::fee_config());
+
+        assert_eq!(buy.data[..8], pump_swap::BUY_EXACT_QUOTE_IN_DISCRIMINATOR);
+        assert_eq!(buy.data[8..16], 1_000_000_000u64.to_le_bytes());
+        // 200M tokens * 1 SOL / 51 SOL, less 5% slippage.
+        let min_out = u64::from_le_bytes(buy.data[16..24].try_into().unwrap());
+        assert_eq!(min_out, 3_725_490_196_077);
+
+        let sell = builder.build_swap_sell(&swap, 1_000).unwrap().unwrap();
+        let sell = &sell.message.instructions[1];
+        assert_eq!(sell.accounts.len(), 21);
+        assert_eq!(sell.data[..8], pump_swap::SELL_DISCRIMINATOR);
     }
 }
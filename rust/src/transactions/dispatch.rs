@@ -1,49 +1,78 @@
-use std::sync::Arc;
+use std::{future::Future, sync::Arc, time::Instant};
 
 use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use futures::future::{select_ok, BoxFuture};
 use reqwest::Client;
-use serde::Deserialize;
 use serde_json::json;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::RpcSendTransactionConfig;
 use solana_sdk::{signature::Signature, transaction::Transaction};
 
-use crate::config::Config;
+use crate::{
+    config::Config,
+    latency::{LatencyStats, Stage},
+};
 
 pub async fn dispatch_transaction(
     transaction: &Transaction,
     config: &Config,
     rpc_client: Arc<RpcClient>,
     http_client: &Client,
+    latency: &LatencyStats,
 ) -> Result<Signature> {
+    let started = Instant::now();
     let serialized = bincode::serialize(transaction)?;
     let encoded = STANDARD.encode(serialized);
 
     let mut futures: Vec<BoxFuture<'static, Result<Signature>>> = Vec::new();
-    futures.push(Box::pin(send_via_rpc(rpc_client, transaction.clone())));
+    futures.push(spawn_timed(
+        latency.clone(),
+        Stage::SendRpc,
+        send_via_rpc(rpc_client, transaction.clone()),
+    ));
 
     if let Some(url) = config.endpoints.jito_api_url.clone() {
-        futures.push(Box::pin(send_via_jito(
-            url,
-            encoded.clone(),
-            http_client.clone(),
-        )));
+        futures.push(spawn_timed(
+            latency.clone(),
+            Stage::SendJito,
+            send_via_jito(url, encoded.clone(), http_client.clone()),
+        ));
     }
 
     if let Some(url) = config.endpoints.nozomi_rpc_url.clone() {
-        futures.push(Box::pin(send_via_http(
-            url,
-            encoded.clone(),
-            http_client.clone(),
-        )));
+        futures.push(spawn_timed(
+            latency.clone(),
+            Stage::SendNozomi,
+            send_via_http(url, encoded.clone(), http_client.clone()),
+        ));
     }
 
-    match select_ok(futures).await {
+    let result = match select_ok(futures).await {
         Ok((sig, _)) => Ok(sig),
         Err(err) => Err(err),
-    }
+    };
+    latency.record(Stage::Dispatch, started.elapsed());
+    result
+}
+
+/// Runs `send` as its own task and records how long it took to finish. The
+/// task outlives the race, so senders that lose it are timed too.
+fn spawn_timed(
+    latency: LatencyStats,
+    stage: Stage,
+    send: impl Future<Output = Result<Signature>> + Send + 'static,
+) -> BoxFuture<'static, Result<Signature>> {
+    let task = tokio::spawn(async move {
+        let started = Instant::now();
+        let output = send.await;
+        latency.record(stage, started.elapsed());
+        output
+    });
+    Box::pin(async move {
+        task.await
+            .map_err(|err| anyhow!("{stage:?} sender failed: {err}"))?
+    })
 }
 
 async fn send_via_rpc(rpc_client: Arc<RpcClient>, transaction: Transaction) -> Result<Signature> {
@@ -59,11 +88,6 @@ async fn send_via_rpc(rpc_client: Arc<RpcClient>, transaction: Transaction) -> R
     Ok(signature)
 }
 
-#[derive(Debug, Deserialize)]
-struct RpcResponse {
-    result: Option<String>,
-}
-
 async fn send_via_jito(url: String, encoded: String, client: Client) -> Result<Signature> {
     // Jito's sendBundle API expects an array of base64-encoded transactions
     let payload = json!({
@@ -85,14 +109,20 @@ async fn send_via_jito(url: String, encoded: String, client: Client) -> Result<S
     // Jito returns bundle IDs, not transaction signatures
     // Extract the first bundle ID from the result array
     if let Some(result) = body.get("result") {
-        if let Some(bundle_id) = result.as_array().and_then(|arr| arr.first()).and_then(|v| v.as_str()) {
+        if let Some(bundle_id) = result
+            .as_array()
+            .and_then(|arr| arr.first())
+            .and_then(|v| v.as_str())
+        {
             // Parse bundle ID as signature (they're both base58 strings)
             let signature: Signature = bundle_id.parse()?;
             return Ok(signature);
         }
     }
 
-    Err(anyhow!("Jito HTTP send failed with status {status}: {body}"))
+    Err(anyhow!(
+        "Jito HTTP send failed with status {status}: {body}"
+    ))
 }
 
 async fn send_via_http(url: String, encoded: String, client: Client) -> Result<Signature> {
@@ -119,3 +149,34 @@ async fn send_via_http(url: String, encoded: String, client: Client) -> Result<S
 
     Err(anyhow!("HTTP send failed with status {status}: {body}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn times_senders_that_lose_the_race() {
+        let latency = LatencyStats::default();
+        let winner = Signature::new_unique();
+        let senders = vec![
+            spawn_timed(latency.clone(), Stage::SendRpc, async move { Ok(winner) }),
+            spawn_timed(latency.clone(), Stage::SendJito, async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(Signature::new_unique())
+            }),
+        ];
+
+        let (signature, _) = select_ok(senders).await.unwrap();
+        assert_eq!(signature, winner);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let timed: Vec<Stage> = latency
+            .snapshot()
+            .into_iter()
+            .filter(|(_, latency)| latency.count == 1)
+            .map(|(stage, _)| stage)
+            .collect();
+        assert_eq!(timed, [Stage::SendRpc, Stage::SendJito]);
+    }
+}